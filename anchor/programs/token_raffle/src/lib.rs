@@ -1,64 +1,177 @@
+#![allow(unexpected_cfgs)]
+// Only for the IDL instruction handlers `#[program]` generates at the crate
+// root, outside the program module, which still call the deprecated
+// `AccountInfo::realloc`. This crate itself must not use deprecated APIs.
+#![allow(deprecated)]
+
 use anchor_lang::prelude::*;
 use anchor_spl::associated_token::AssociatedToken;
 use anchor_spl::metadata::{create_metadata_accounts_v3, CreateMetadataAccountsV3, Metadata};
-use anchor_spl::token::{self, mint_to, transfer, MintTo, Transfer};
 use anchor_lang::solana_program::{keccak, sysvar::clock::Clock};
+use anchor_lang::system_program;
 // use anchor_lang::solana_program::program;
-use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use anchor_spl::associated_token::{self, Create};
+use anchor_spl::token_2022::spl_token_2022::extension::ExtensionType;
+use anchor_spl::token_2022::spl_token_2022::instruction::AuthorityType;
+use anchor_spl::token_2022::spl_token_2022::state::Mint as Token2022Mint;
+use anchor_spl::token_2022::{self, InitializeMint2, SetAuthority, Token2022};
+use anchor_spl::token_interface::spl_pod::optional_keys::OptionalNonZeroPubkey;
+use anchor_spl::token_interface::spl_token_metadata_interface::state::TokenMetadata;
+use anchor_spl::token_interface::{
+    self, burn, freeze_account, metadata_pointer_initialize, mint_to,
+    non_transferable_mint_initialize, thaw_account, token_metadata_initialize,
+    transfer_hook_initialize, Burn, FreezeAccount, MetadataPointerInitialize, Mint, MintTo,
+    NonTransferableMintInitialize, ThawAccount, TokenAccount, TokenInterface,
+    TokenMetadataInitialize, TransferHookInitialize,
+};
+
+use switchboard_on_demand::{RandomnessAccountData, ON_DEMAND_DEVNET_PID, ON_DEMAND_MAINNET_PID};
+
+use mpl_bubblegum::instructions::{CreateTreeConfigCpiBuilder, MintToCollectionV1CpiBuilder};
+use mpl_bubblegum::types::{LeafSchema, MetadataArgs, TokenProgramVersion, TokenStandard};
+
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke;
+use anchor_lang::solana_program::{ed25519_program, sysvar::instructions as instructions_sysvar};
 use mpl_token_metadata::accounts::Metadata as MetadataAccount;
+use mpl_token_metadata::types::Key as MetadataKey;
 
-use anchor_lang::system_program;
 use anchor_spl::metadata::{
     create_master_edition_v3,
-    mpl_token_metadata::types::{CollectionDetails, Creator, DataV2},
-    sign_metadata, CreateMasterEditionV3, SignMetadata,
+    mpl_token_metadata::types::{Collection, CollectionDetails, Creator, DataV2},
+    sign_metadata, verify_sized_collection_item, CreateMasterEditionV3, SignMetadata,
+    VerifySizedCollectionItem,
 };
 
 declare_id!("8xQ1B6beBjoP9oFRHzjmPyHzdGAJPnxcUYzL6Dr5Vsax");
 
+// Metaplex Token Metadata limits for `DataV2` strings. Not `#[constant]`: the
+// IDL has no `usize` type.
+pub const MAX_NAME_LENGTH: usize = 32;
+pub const MAX_SYMBOL_LENGTH: usize = 10;
+pub const MAX_URI_LENGTH: usize = 200;
+
 #[constant]
-pub const NAME: &str = "Token Lottery Ticket #";
-#[constant]
-pub const URI: &str = "Token Lottery";
+pub const MAX_BASIS_POINTS: u16 = 10_000;
+/// Metaplex allows 5 creators; one slot is reserved for the mint authority PDA
+pub const MAX_ROYALTY_CREATORS: usize = 4;
+pub const MAX_BULK_DISCOUNTS: usize = 4;
+
+pub const SPL_ACCOUNT_COMPRESSION_ID: Pubkey =
+    pubkey!("cmtDvXumGCrqC1Age74AVPhSRVXJMd8PJS91L8KbNCK");
+pub const SPL_NOOP_ID: Pubkey = pubkey!("noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV");
+
+/// Anchor discriminator of spl-account-compression `verify_leaf`
+const VERIFY_LEAF_DISCRIMINATOR: [u8; 8] = [124, 220, 22, 223, 104, 10, 250, 224];
+
+/// Placeholder in `NftConfig::ticket_uri` replaced with the ticket index
 #[constant]
-pub const SYMBOL: &str = "TICKET";
+pub const TICKET_ID_PLACEHOLDER: &str = "{id}";
 
 #[program]
 pub mod token_raffle {
 
     use super::*;
 
+    #[allow(clippy::too_many_arguments)]
+    /// Creates a draft raffle under the next sequential `raffle_id` from the
+    /// registry. It cannot take entries until `init_raffle` has created its
+    /// collection and `open_raffle` has opened it.
     pub fn init_config(
         ctx: Context<InitConfig>,
-        raffle_id: u64,
         name: String,
         start: u64,
         end: i64,
         price: u64,
         max_tickets: u64,
+        nft_config: NftConfig,
+        winner_by_holder: bool,
     ) -> Result<()> {
-        ctx.accounts.raffle.authority = ctx.accounts.payer.key();
+        require!(!ctx.accounts.protocol_config.paused, ErrorCode::ProtocolPaused);
+        require!(name.len() <= MAX_NAME_LENGTH, ErrorCode::NameTooLong);
+        nft_config.validate(max_tickets)?;
+
+        let registry = &mut ctx.accounts.raffle_registry;
+        let raffle_id = registry.next_raffle_id;
+        registry.next_raffle_id = raffle_id.checked_add(1).ok_or(ErrorCode::Overflow)?;
+
+        let authority_raffles = &mut ctx.accounts.authority_raffles;
+        authority_raffles.authority = ctx.accounts.authority.key();
+        authority_raffles.bump = ctx.bumps.authority_raffles;
+        authority_raffles.raffle_count = authority_raffles
+            .raffle_count
+            .checked_add(1)
+            .ok_or(ErrorCode::Overflow)?;
+
+        let raffle_index = &mut ctx.accounts.raffle_index;
+        raffle_index.raffle = ctx.accounts.raffle.key();
+        raffle_index.creator = ctx.accounts.authority.key();
+        raffle_index.bump = ctx.bumps.raffle_index;
+
+        ctx.accounts.raffle.authority = ctx.accounts.authority.key();
+        ctx.accounts.raffle.creator = ctx.accounts.authority.key();
+        ctx.accounts.raffle.pending_authority = None;
+        ctx.accounts.raffle.operator = None;
+        ctx.accounts.raffle.paused = false;
+        ctx.accounts.raffle.registration_period = 0;
+        ctx.accounts.raffle.randomness_account = None;
         ctx.accounts.raffle.bump = ctx.bumps.raffle;
 
         ctx.accounts.raffle.name = name;
-        ctx.accounts.raffle.is_active = true;
+        ctx.accounts.raffle.state = RaffleState::Draft;
         ctx.accounts.raffle.raffle_id = raffle_id;
         ctx.accounts.raffle.start_time = start;
         ctx.accounts.raffle.end_time = end;
         ctx.accounts.raffle.price = price;
         ctx.accounts.raffle.randomness = [0u8; 32];
 
-        ctx.accounts.raffle.winner_chosen = false;
         ctx.accounts.raffle.total_num_tickets_bought = 0;
         ctx.accounts.raffle.max_tickets = max_tickets;
         ctx.accounts.raffle.prize_amount = 10;
-        ctx.accounts.raffle.claimed = false;
-        ctx.accounts.raffle.randomness_committed = false;
+        ctx.accounts.raffle.nft_config = nft_config;
+        ctx.accounts.raffle.ticket_mode = TicketMode::Nft;
+        ctx.accounts.raffle.merkle_tree = Pubkey::default();
+        ctx.accounts.raffle.ticket_mint = Pubkey::default();
+        ctx.accounts.raffle.snapshotted_tickets = 0;
+        ctx.accounts.raffle.freeze_after_sale = false;
+        ctx.accounts.raffle.winner_by_holder = winner_by_holder;
+        ctx.accounts.raffle.winning_ticket_mint = Pubkey::default();
+        ctx.accounts.raffle.ticket_non_transferable = false;
+        ctx.accounts.raffle.transfer_hook_program = None;
+        ctx.accounts.raffle.badge_mint = Pubkey::default();
+        ctx.accounts.raffle.allowlist_root = [0u8; 32];
+        ctx.accounts.raffle.gate_collection = None;
+        ctx.accounts.raffle.gate_mint = None;
+        ctx.accounts.raffle.gate_min_amount = 0;
+        ctx.accounts.raffle.burn_mint = None;
+        ctx.accounts.raffle.total_burned = 0;
+        ctx.accounts.raffle.entry_signer = None;
+        ctx.accounts.raffle.one_entry_per_wallet = false;
+        ctx.accounts.raffle.referral_bps = 0;
+        ctx.accounts.raffle.referral_vault = Pubkey::default();
+        ctx.accounts.raffle.pricing = PricingSchedule::default();
+        ctx.accounts.raffle.collection_initialized = false;
+        ctx.accounts.raffle.extension_policy = ExtensionPolicy::default();
+        ctx.accounts.raffle.extensions_used = 0;
+
+        emit_cpi!(RaffleCreated {
+            raffle_id,
+            raffle: ctx.accounts.raffle.key(),
+            authority: ctx.accounts.authority.key(),
+            start_time: start,
+            end_time: end,
+            price,
+            max_tickets,
+        });
 
         Ok(())
     }
 
-    pub fn init_raffle(ctx: Context<InitRaffle>) -> Result<()> {
+    pub fn init_raffle(ctx: Context<InitRaffle>, royalty: RoyaltyConfig) -> Result<()> {
+        ctx.accounts.raffle.require_state(&[RaffleState::Draft])?;
+        royalty.validate()?;
+        ctx.accounts.raffle.royalty = royalty;
+
         let ma_bump = ctx.bumps.mint_authority;
         let raffle = &ctx.accounts.raffle;
 
@@ -95,15 +208,11 @@ pub mod token_raffle {
                 signer,
             ),
             DataV2 {
-                name: NAME.to_string(),
-                symbol: SYMBOL.to_string(),
-                uri: URI.to_string(),
-                seller_fee_basis_points: 0,
-                creators: Some(vec![Creator {
-                    address: ctx.accounts.mint_authority.key(),
-                    verified: true,
-                    share: 100,
-                }]),
+                name: raffle.name.clone(),
+                symbol: raffle.nft_config.symbol.clone(),
+                uri: raffle.nft_config.collection_uri.clone(),
+                seller_fee_basis_points: raffle.royalty.seller_fee_basis_points,
+                creators: Some(raffle.metaplex_creators(ctx.accounts.mint_authority.key(), true)),
                 collection: None,
                 uses: None,
             },
@@ -141,27 +250,45 @@ pub mod token_raffle {
             signer,
         ))?;
 
+        ctx.accounts.raffle.collection_initialized = true;
+
+        emit_cpi!(CollectionInitialized {
+            raffle_id: ctx.accounts.raffle.raffle_id,
+            collection_mint: ctx.accounts.collection_mint.key(),
+        });
+
         Ok(())
     }
 
-    pub fn buy_tickets(ctx: Context<BuyTickets>) -> Result<()> {
-        let raffle = &mut ctx.accounts.raffle;
-
-        // Make sure tickets are still available
+    pub fn buy_tickets(
+        ctx: Context<BuyTickets>,
+        allowlist_proof: Option<AllowlistProof>,
+        entry_nonce: Option<u64>,
+    ) -> Result<()> {
         require!(
-            raffle.total_num_tickets_bought < raffle.max_tickets,
-            ErrorCode::NoTicketsLeft
+            ctx.accounts.raffle.ticket_mode == TicketMode::Nft,
+            ErrorCode::InvalidTicketMode
         );
+        ctx.accounts
+            .purchase()
+            .check(1, allowlist_proof, entry_nonce)?;
+        let raffle = &ctx.accounts.raffle;
+
+        let ticket_index = raffle.total_num_tickets_bought;
 
         // Mint 1 ticket token to the user
 
-        let binding = ctx.accounts.payer.key();
-        let signer_seeds: &[&[&[u8]]] = &[&[
-            b"raffle",
-            binding.as_ref(),
-            &raffle.raffle_id.to_le_bytes(),
-            &[raffle.bump],
-        ]];
+        let creator = raffle.creator;
+        let raffle_id = raffle.raffle_id.to_le_bytes();
+        let raffle_seeds: &[&[u8]] = &[b"raffle", creator.as_ref(), &raffle_id, &[raffle.bump]];
+
+        let raffle_key = raffle.key();
+        let mint_authority_seeds: &[&[u8]] = &[
+            b"mint_authority",
+            raffle_key.as_ref(),
+            &[ctx.bumps.mint_authority],
+        ];
+        let signer_seeds: &[&[&[u8]]] = &[raffle_seeds, mint_authority_seeds];
 
         let cpi_accounts = MintTo {
             mint: ctx.accounts.ticket_mint.to_account_info(),
@@ -175,506 +302,3540 @@ pub mod token_raffle {
             signer_seeds,
         );
 
-        token::mint_to(cpi_context, 1)?;
+        mint_to(cpi_context, 1)?;
+
+        // Ticket metadata, rendered from the raffle's NFT config
+        create_metadata_accounts_v3(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_metadata_program.to_account_info(),
+                CreateMetadataAccountsV3 {
+                    metadata: ctx.accounts.ticket_metadata.to_account_info(),
+                    mint: ctx.accounts.ticket_mint.to_account_info(),
+                    mint_authority: raffle.to_account_info(),
+                    payer: ctx.accounts.payer.to_account_info(),
+                    update_authority: ctx.accounts.mint_authority.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    rent: ctx.accounts.rent.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            DataV2 {
+                name: raffle.nft_config.ticket_name(ticket_index),
+                symbol: raffle.nft_config.symbol.clone(),
+                uri: raffle.nft_config.ticket_uri(ticket_index),
+                seller_fee_basis_points: raffle.royalty.seller_fee_basis_points,
+                creators: Some(raffle.metaplex_creators(ctx.accounts.mint_authority.key(), true)),
+                collection: Some(Collection {
+                    verified: false,
+                    key: ctx.accounts.mint.key(),
+                }),
+                uses: None,
+            },
+            true,
+            true,
+            None,
+        )?;
 
-        raffle.ticket_numbers.push(ctx.accounts.payer.key());
+        create_master_edition_v3(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_metadata_program.to_account_info(),
+                CreateMasterEditionV3 {
+                    edition: ctx.accounts.ticket_master_edition.to_account_info(),
+                    mint: ctx.accounts.ticket_mint.to_account_info(),
+                    update_authority: ctx.accounts.mint_authority.to_account_info(),
+                    mint_authority: raffle.to_account_info(),
+                    payer: ctx.accounts.payer.to_account_info(),
+                    metadata: ctx.accounts.ticket_metadata.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    rent: ctx.accounts.rent.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            Some(0),
+        )?;
 
-        // Update number of tickets bought
-        raffle.total_num_tickets_bought = raffle
-            .total_num_tickets_bought
-            .checked_add(1)
-            .ok_or(ErrorCode::Overflow)?;
+        // Verify the ticket as a member of the raffle collection
+        verify_sized_collection_item(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_metadata_program.to_account_info(),
+                VerifySizedCollectionItem {
+                    payer: ctx.accounts.payer.to_account_info(),
+                    metadata: ctx.accounts.ticket_metadata.to_account_info(),
+                    collection_authority: ctx.accounts.mint_authority.to_account_info(),
+                    collection_mint: ctx.accounts.mint.to_account_info(),
+                    collection_metadata: ctx.accounts.collection_metadata.to_account_info(),
+                    collection_master_edition: ctx
+                        .accounts
+                        .collection_master_edition
+                        .to_account_info(),
+                },
+                signer_seeds,
+            ),
+            None,
+        )?;
+
+        let (tickets_purchased, end_time_changed) = ctx
+            .accounts
+            .purchase()
+            .complete(ctx.bumps.participant, 1)?;
+        ctx.accounts
+            .raffle
+            .ticket_numbers
+            .push(ctx.accounts.payer.key());
+        emit_cpi!(tickets_purchased);
+        if let Some(end_time_changed) = end_time_changed {
+            emit_cpi!(end_time_changed);
+        }
 
         Ok(())
     }
 
-pub fn commit_randomness(ctx: Context<CommitRandomness>, use_mock: bool) -> Result<()> {
-    let raffle = &mut ctx.accounts.raffle;
-    let clock = Clock::get()?;
-
-    require!(
-        ctx.accounts.payer.key() == raffle.authority,
-        ErrorCode::NotAuthorized
-    );
+    /// Switches a raffle to compressed tickets, minted into `merkle_tree` via
+    /// Bubblegum. The tree account must be pre-allocated by the client and owned
+    /// by spl-account-compression.
+    pub fn init_ticket_tree(
+        ctx: Context<InitTicketTree>,
+        max_depth: u32,
+        max_buffer_size: u32,
+    ) -> Result<()> {
+        ctx.accounts
+            .raffle
+            .require_state(&[RaffleState::Draft, RaffleState::Open])?;
+        let raffle = &ctx.accounts.raffle;
 
-    require!(
-        clock.unix_timestamp >= raffle.end_time,
-        ErrorCode::RaffleStillActive
-    );
+        raffle.require_no_sales()?;
+        require!(
+            max_depth < u64::BITS && raffle.max_tickets <= 1u64 << max_depth,
+            ErrorCode::TreeTooSmall
+        );
 
-    require!(raffle.is_active, ErrorCode::RaffleNotActive);
+        let raffle_key = raffle.key();
+        let mint_authority_seeds: &[&[u8]] = &[
+            b"mint_authority",
+            raffle_key.as_ref(),
+            &[ctx.bumps.mint_authority],
+        ];
+
+        CreateTreeConfigCpiBuilder::new(&ctx.accounts.bubblegum_program.to_account_info())
+            .tree_config(&ctx.accounts.tree_config.to_account_info())
+            .merkle_tree(&ctx.accounts.merkle_tree.to_account_info())
+            .payer(&ctx.accounts.payer.to_account_info())
+            .tree_creator(&ctx.accounts.mint_authority.to_account_info())
+            .log_wrapper(&ctx.accounts.log_wrapper.to_account_info())
+            .compression_program(&ctx.accounts.compression_program.to_account_info())
+            .system_program(&ctx.accounts.system_program.to_account_info())
+            .max_depth(max_depth)
+            .max_buffer_size(max_buffer_size)
+            .public(false)
+            .invoke_signed(&[mint_authority_seeds])?;
 
-    if use_mock {
-        // ✅ Deterministic mock randomness
-        let combined_value = (clock.unix_timestamp as u128)
-            .wrapping_mul(raffle.raffle_id as u128)
-            .wrapping_add(clock.slot as u128);
+        let raffle = &mut ctx.accounts.raffle;
+        raffle.ticket_mode = TicketMode::Compressed;
+        raffle.merkle_tree = ctx.accounts.merkle_tree.key();
 
-        let randomness_bytes = combined_value.to_le_bytes();
-        raffle.randomness[..16].copy_from_slice(&randomness_bytes);
+        Ok(())
+    }
 
-                raffle.randomness_committed = true;
+    /// Mints one compressed ticket to the buyer. The tree is private to the
+    /// raffle, so the leaf index equals the ticket index.
+    pub fn buy_compressed_tickets(
+        ctx: Context<BuyCompressedTickets>,
+        allowlist_proof: Option<AllowlistProof>,
+        entry_nonce: Option<u64>,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.raffle.ticket_mode == TicketMode::Compressed,
+            ErrorCode::InvalidTicketMode
+        );
+        ctx.accounts
+            .purchase()
+            .check(1, allowlist_proof, entry_nonce)?;
+        let raffle = &ctx.accounts.raffle;
 
+        let ticket_index = raffle.total_num_tickets_bought;
+
+        let raffle_key = raffle.key();
+        let mint_authority_seeds: &[&[u8]] = &[
+            b"mint_authority",
+            raffle_key.as_ref(),
+            &[ctx.bumps.mint_authority],
+        ];
+
+        let creators = raffle
+            .metaplex_creators(ctx.accounts.mint_authority.key(), true)
+            .into_iter()
+            .map(|creator| mpl_bubblegum::types::Creator {
+                address: creator.address,
+                verified: creator.verified,
+                share: creator.share,
+            })
+            .collect();
+
+        let metadata = MetadataArgs {
+            name: raffle.nft_config.ticket_name(ticket_index),
+            symbol: raffle.nft_config.symbol.clone(),
+            uri: raffle.nft_config.ticket_uri(ticket_index),
+            seller_fee_basis_points: raffle.royalty.seller_fee_basis_points,
+            primary_sale_happened: false,
+            is_mutable: true,
+            edition_nonce: None,
+            token_standard: Some(TokenStandard::NonFungible),
+            collection: Some(mpl_bubblegum::types::Collection {
+                verified: false,
+                key: ctx.accounts.collection_mint.key(),
+            }),
+            uses: None,
+            token_program_version: TokenProgramVersion::Original,
+            creators,
+        };
 
-        msg!("Mock randomness committed: {:?}", raffle.randomness);
-    } else {
-        // ✅ Ensure randomness account exists
-        let randomness_ai = ctx
+        let payer = ctx.accounts.payer.to_account_info();
+        let mint_authority = ctx.accounts.mint_authority.to_account_info();
+        let bubblegum_program = ctx.accounts.bubblegum_program.to_account_info();
+
+        MintToCollectionV1CpiBuilder::new(&bubblegum_program)
+            .tree_config(&ctx.accounts.tree_config.to_account_info())
+            .leaf_owner(&payer)
+            .leaf_delegate(&payer)
+            .merkle_tree(&ctx.accounts.merkle_tree.to_account_info())
+            .payer(&payer)
+            .tree_creator_or_delegate(&mint_authority)
+            .collection_authority(&mint_authority)
+            .collection_mint(&ctx.accounts.collection_mint.to_account_info())
+            .collection_metadata(&ctx.accounts.collection_metadata.to_account_info())
+            .collection_edition(&ctx.accounts.collection_master_edition.to_account_info())
+            .bubblegum_signer(&ctx.accounts.bubblegum_signer.to_account_info())
+            .log_wrapper(&ctx.accounts.log_wrapper.to_account_info())
+            .compression_program(&ctx.accounts.compression_program.to_account_info())
+            .token_metadata_program(&ctx.accounts.token_metadata_program.to_account_info())
+            .system_program(&ctx.accounts.system_program.to_account_info())
+            .metadata(metadata)
+            .invoke_signed(&[mint_authority_seeds])?;
+
+        // Buyers are not recorded in `ticket_numbers`; ownership is proven by
+        // leaf proof at claim time
+        let (tickets_purchased, end_time_changed) = ctx
             .accounts
-            .randomness_data_account
-            .as_ref()
-            .ok_or(ErrorCode::MissingRandomnessAccount)?;
-
-        let data = randomness_ai.try_borrow_data()?;
-        require!(data.len() >= 40, ErrorCode::InvalidRandomnessAccount);
-
-        let seed_bytes: [u8; 32] = data[8..40]
-            .try_into()
-            .map_err(|_| ErrorCode::InvalidRandomnessAccount)?;
-        raffle.randomness = seed_bytes;
-
-        raffle.randomness_committed = true;
+            .purchase()
+            .complete(ctx.bumps.participant, 1)?;
+        emit_cpi!(tickets_purchased);
+        if let Some(end_time_changed) = end_time_changed {
+            emit_cpi!(end_time_changed);
+        }
 
-        msg!("Real randomness committed: {:?}", raffle.randomness);
+        Ok(())
     }
 
-    Ok(())
-}
-
-    pub fn reveal_winner(ctx: Context<RevealWinner>) -> Result<()> {
+    /// Switches a raffle to fungible tickets: a single decimals-0 mint where
+    /// each token is one ticket. With `freeze_after_sale`, ticket accounts can
+    /// be frozen by `freeze_tickets` once `end_time` passes. Holders have
+    /// `registration_period` seconds after `end_time` to register.
+    pub fn init_fungible_tickets(
+        ctx: Context<InitFungibleTickets>,
+        freeze_after_sale: bool,
+        registration_period: i64,
+    ) -> Result<()> {
+        ctx.accounts
+            .raffle
+            .require_state(&[RaffleState::Draft, RaffleState::Open])?;
         let raffle = &mut ctx.accounts.raffle;
 
-                    let clock = Clock::get()?;
+        raffle.require_no_sales()?;
 
-    require!(
-        ctx.accounts.payer.key() == raffle.authority,
-        ErrorCode::NotAuthorized
-    );
+        require!(
+            registration_period > 0,
+            ErrorCode::InvalidRegistrationPeriod
+        );
 
-    // ✅ Prevent committing before raffle ends
-    require!(
-        clock.unix_timestamp >= raffle.end_time,
-        ErrorCode::RaffleStillActive
-    );
+        raffle.ticket_mode = TicketMode::Fungible;
+        raffle.ticket_mint = ctx.accounts.ticket_mint.key();
+        raffle.freeze_after_sale = freeze_after_sale;
+        raffle.registration_period = registration_period;
+
+        Ok(())
+    }
 
-                require!(raffle.is_active, ErrorCode::RaffleNotActive);
+    pub fn buy_fungible_tickets(
+        ctx: Context<BuyFungibleTickets>,
+        amount: u64,
+        allowlist_proof: Option<AllowlistProof>,
+        entry_nonce: Option<u64>,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.raffle.ticket_mode == TicketMode::Fungible,
+            ErrorCode::InvalidTicketMode
+        );
+        require!(amount > 0, ErrorCode::InvalidTicketAmount);
+        ctx.accounts
+            .purchase()
+            .check(amount, allowlist_proof, entry_nonce)?;
+        let raffle = &ctx.accounts.raffle;
 
-                require!(
-        raffle.randomness_committed,
-        ErrorCode::RandomnessNotCommitted
-    );
+        let creator = raffle.creator;
+        let raffle_id = raffle.raffle_id.to_le_bytes();
+        let raffle_seeds: &[&[u8]] = &[b"raffle", creator.as_ref(), &raffle_id, &[raffle.bump]];
+
+        mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.ticket_mint.to_account_info(),
+                    to: ctx.accounts.user_token_account.to_account_info(),
+                    authority: raffle.to_account_info(),
+                },
+                &[raffle_seeds],
+            ),
+            amount,
+        )?;
 
-    
+        let (tickets_purchased, end_time_changed) = ctx
+            .accounts
+            .purchase()
+            .complete(ctx.bumps.participant, amount)?;
+        emit_cpi!(tickets_purchased);
+        if let Some(end_time_changed) = end_time_changed {
+            emit_cpi!(end_time_changed);
+        }
 
+        Ok(())
+    }
 
+    /// Registers the holder's fungible ticket balance for the draw between
+    /// `end_time` and the registration deadline. The tokens are burned so the
+    /// same tickets cannot be registered twice; the holder gets the index range
+    /// `[range_start, range_start + amount)`. Each registration gets its own
+    /// snapshot, so a holder can register more tickets later.
+    pub fn snapshot_tickets(ctx: Context<SnapshotTickets>) -> Result<()> {
+        let raffle = &ctx.accounts.raffle;
 
-        require!(!raffle.winner_chosen, ErrorCode::WinnerAlreadyChosen);
-        require!(
-            raffle.total_num_tickets_bought > 0,
-            ErrorCode::NoTicketsBought
-        );
         require!(
-            raffle.randomness != [0u8; 32],
-            ErrorCode::RandomnessNotCommitted
+            raffle.ticket_mode == TicketMode::Fungible,
+            ErrorCode::InvalidTicketMode
         );
+        raffle.require_state(&[RaffleState::Closed])?;
         require!(
-            raffle.ticket_numbers.len() == raffle.total_num_tickets_bought as usize,
-            ErrorCode::InvalidTicketData
+            Clock::get()?.unix_timestamp < raffle.registration_deadline(),
+            ErrorCode::RegistrationClosed
         );
 
-        // Convert first 16 bytes of randomness to u128
-        let mut bytes = [0u8; 16];
-        bytes.copy_from_slice(&raffle.randomness[..16]);
-        let random_value = u128::from_le_bytes(bytes);
+        let amount = ctx.accounts.holder_token_account.amount;
+        require!(amount > 0, ErrorCode::InvalidTicketAmount);
 
-        // Winner index
-        let winner_index = (random_value % raffle.ticket_numbers.len() as u128) as usize;
-        let winner_pubkey = raffle.ticket_numbers[winner_index];
+        // Accounts frozen by `freeze_tickets` are thawed only to be burned
+        if ctx.accounts.holder_token_account.is_frozen() {
+            let creator = raffle.creator;
+            let raffle_id = raffle.raffle_id.to_le_bytes();
+            let raffle_seeds: &[&[u8]] =
+                &[b"raffle", creator.as_ref(), &raffle_id, &[raffle.bump]];
 
-        // Set winner details
-        raffle.winner = winner_pubkey;
-        raffle.winner_index = Some(winner_index as u64);
-        raffle.winner_chosen = true;
-        raffle.is_active = false;
+            thaw_account(CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                ThawAccount {
+                    account: ctx.accounts.holder_token_account.to_account_info(),
+                    mint: ctx.accounts.ticket_mint.to_account_info(),
+                    authority: raffle.to_account_info(),
+                },
+                &[raffle_seeds],
+            ))?;
+        }
 
-        emit!(WinnerChosen {
-            raffle_id: raffle.raffle_id,
-            winner: winner_pubkey,
-            winner_index: winner_index as u64,
-        });
+        burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.ticket_mint.to_account_info(),
+                    from: ctx.accounts.holder_token_account.to_account_info(),
+                    authority: ctx.accounts.holder.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let raffle = &mut ctx.accounts.raffle;
+        let snapshot = &mut ctx.accounts.ticket_snapshot;
+        snapshot.raffle = raffle.key();
+        snapshot.owner = ctx.accounts.holder.key();
+        snapshot.range_start = raffle.snapshotted_tickets;
+        snapshot.amount = amount;
+        snapshot.bump = ctx.bumps.ticket_snapshot;
+
+        raffle.snapshotted_tickets = raffle
+            .snapshotted_tickets
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
 
         Ok(())
     }
 
-    pub fn claim_prize(ctx: Context<ClaimPrize>) -> Result<()> {
+    /// Switches a raffle to Token-2022 tickets: one mint per ticket carrying its
+    /// own metadata through the metadata-pointer and token-metadata extensions,
+    /// so no Metaplex accounts are needed. Tickets can optionally be made
+    /// non-transferable or route transfers through a transfer-hook program.
+    pub fn init_token2022_tickets(
+        ctx: Context<InitToken2022Tickets>,
+        non_transferable: bool,
+        transfer_hook_program: Option<Pubkey>,
+    ) -> Result<()> {
+        ctx.accounts
+            .raffle
+            .require_state(&[RaffleState::Draft, RaffleState::Open])?;
         let raffle = &mut ctx.accounts.raffle;
-            let clock = Clock::get()?;
 
-          require!(
-        clock.unix_timestamp >= raffle.end_time,
-        ErrorCode::RaffleStillActive
-    );
-
-    
+        raffle.require_no_sales()?;
 
-require!(!raffle.claimed, ErrorCode::AlreadyClaimed);
+        raffle.ticket_mode = TicketMode::Token2022;
+        raffle.ticket_non_transferable = non_transferable;
+        raffle.transfer_hook_program = transfer_hook_program;
 
+        Ok(())
+    }
 
-require!(raffle.winner_chosen, ErrorCode::WinnerNotChosen);
-
+    pub fn buy_token2022_tickets(
+        ctx: Context<BuyToken2022Tickets>,
+        allowlist_proof: Option<AllowlistProof>,
+        entry_nonce: Option<u64>,
+    ) -> Result<()> {
         require!(
-raffle.randomness != [0u8; 32],
-ErrorCode::RandomnessNotCommitted
-);
-        // ✅ Check winner conditions here...
+            ctx.accounts.raffle.ticket_mode == TicketMode::Token2022,
+            ErrorCode::InvalidTicketMode
+        );
+        ctx.accounts
+            .purchase()
+            .check(1, allowlist_proof, entry_nonce)?;
+        let raffle = &ctx.accounts.raffle;
 
+        let ticket_index = raffle.total_num_tickets_bought;
+        let ticket_mint_key = ctx.accounts.ticket_mint.key();
+        let mint_authority_key = ctx.accounts.mint_authority.key();
+
+        let creator = raffle.creator;
+        let raffle_id = raffle.raffle_id.to_le_bytes();
+        let raffle_seeds: &[&[u8]] = &[b"raffle", creator.as_ref(), &raffle_id, &[raffle.bump]];
+
+        let raffle_key = raffle.key();
+        let ticket_index_bytes = ticket_index.to_le_bytes();
+        let ticket_mint_seeds: &[&[u8]] = &[
+            b"ticket_mint",
+            raffle_key.as_ref(),
+            &ticket_index_bytes,
+            &[ctx.bumps.ticket_mint],
+        ];
+
+        let mut extensions = vec![ExtensionType::MetadataPointer];
+        if raffle.ticket_non_transferable {
+            extensions.push(ExtensionType::NonTransferable);
+        }
+        if raffle.transfer_hook_program.is_some() {
+            extensions.push(ExtensionType::TransferHook);
+        }
+        let mint_len = ExtensionType::try_calculate_account_len::<Token2022Mint>(&extensions)?;
+
+        let metadata = TokenMetadata {
+            update_authority: OptionalNonZeroPubkey::try_from(Some(mint_authority_key))?,
+            mint: ticket_mint_key,
+            name: raffle.nft_config.ticket_name(ticket_index),
+            symbol: raffle.nft_config.symbol.clone(),
+            uri: raffle.nft_config.ticket_uri(ticket_index),
+            additional_metadata: vec![],
+        };
+
+        // The mint is allocated without the metadata TLV, which token-metadata
+        // reallocs into, so it is funded for both up front
+        let lamports = Rent::get()?.minimum_balance(mint_len + metadata.tlv_size_of()?);
+
+        create_pda_account(
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.ticket_mint.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            lamports,
+            mint_len as u64,
+            &ctx.accounts.token_program.key(),
+            ticket_mint_seeds,
+        )?;
 
+        metadata_pointer_initialize(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                MetadataPointerInitialize {
+                    token_program_id: ctx.accounts.token_program.to_account_info(),
+                    mint: ctx.accounts.ticket_mint.to_account_info(),
+                },
+            ),
+            Some(mint_authority_key),
+            Some(ticket_mint_key),
+        )?;
 
+        if raffle.ticket_non_transferable {
+            non_transferable_mint_initialize(CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                NonTransferableMintInitialize {
+                    token_program_id: ctx.accounts.token_program.to_account_info(),
+                    mint: ctx.accounts.ticket_mint.to_account_info(),
+                },
+            ))?;
+        }
+
+        if let Some(transfer_hook_program) = raffle.transfer_hook_program {
+            transfer_hook_initialize(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    TransferHookInitialize {
+                        token_program_id: ctx.accounts.token_program.to_account_info(),
+                        mint: ctx.accounts.ticket_mint.to_account_info(),
+                    },
+                ),
+                Some(mint_authority_key),
+                Some(transfer_hook_program),
+            )?;
+        }
+
+        token_2022::initialize_mint2(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                InitializeMint2 {
+                    mint: ctx.accounts.ticket_mint.to_account_info(),
+                },
+            ),
+            0,
+            &raffle_key,
+            Some(&raffle_key),
+        )?;
 
-        // ✅ Mint 1 token to winner
-        let cpi_accounts = MintTo {
-            mint: ctx.accounts.prize_mint.to_account_info(),
-            to: ctx.accounts.winner_ata.to_account_info(),
-            authority: ctx.accounts.prize_mint.to_account_info(), // PDA as authority
-        };
+        token_metadata_initialize(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TokenMetadataInitialize {
+                    program_id: ctx.accounts.token_program.to_account_info(),
+                    metadata: ctx.accounts.ticket_mint.to_account_info(),
+                    update_authority: ctx.accounts.mint_authority.to_account_info(),
+                    mint_authority: raffle.to_account_info(),
+                    mint: ctx.accounts.ticket_mint.to_account_info(),
+                },
+                &[raffle_seeds],
+            ),
+            metadata.name,
+            metadata.symbol,
+            metadata.uri,
+        )?;
 
-        let binding = raffle.key();
-        let seeds: &[&[u8]] = &[b"prize_mint", binding.as_ref(), &[ctx.bumps.prize_mint]];
-        let signer_seeds = &[&seeds[..]];
+        associated_token::create(CpiContext::new(
+            ctx.accounts.associated_token_program.to_account_info(),
+            Create {
+                payer: ctx.accounts.payer.to_account_info(),
+                associated_token: ctx.accounts.user_token_account.to_account_info(),
+                authority: ctx.accounts.payer.to_account_info(),
+                mint: ctx.accounts.ticket_mint.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+            },
+        ))?;
 
-        token::mint_to(
+        token_2022::mint_to(
             CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
-                cpi_accounts,
-                signer_seeds,
+                token_2022::MintTo {
+                    mint: ctx.accounts.ticket_mint.to_account_info(),
+                    to: ctx.accounts.user_token_account.to_account_info(),
+                    authority: raffle.to_account_info(),
+                },
+                &[raffle_seeds],
             ),
             1,
         )?;
 
-        // ✅ Create Metadata using CPI
-        let data = DataV2 {
-            seller_fee_basis_points: 0,
-            creators: None,
-            collection: None,
-            uses: None,
-            name: NAME.to_string(),
-            symbol: SYMBOL.to_string(),
-            uri: URI.to_string(),
-        };
+        // Fix the supply at one so the ticket is a true NFT
+        token_2022::set_authority(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                SetAuthority {
+                    current_authority: raffle.to_account_info(),
+                    account_or_mint: ctx.accounts.ticket_mint.to_account_info(),
+                },
+                &[raffle_seeds],
+            ),
+            AuthorityType::MintTokens,
+            None,
+        )?;
 
-        let metadata_accounts = CreateMetadataAccountsV3 {
-            metadata: ctx.accounts.metadata.to_account_info(),
-            mint: ctx.accounts.prize_mint.to_account_info(),
-            mint_authority: ctx.accounts.prize_mint.to_account_info(), // PDA as mint authority
-            payer: ctx.accounts.winner.to_account_info(),
-            update_authority: ctx.accounts.prize_mint.to_account_info(), // PDA as update authority
-            system_program: ctx.accounts.system_program.to_account_info(),
-            rent: ctx.accounts.rent.to_account_info(),
-        };
+        let (tickets_purchased, end_time_changed) = ctx
+            .accounts
+            .purchase()
+            .complete(ctx.bumps.participant, 1)?;
+        ctx.accounts
+            .raffle
+            .ticket_numbers
+            .push(ctx.accounts.payer.key());
+        emit_cpi!(tickets_purchased);
+        if let Some(end_time_changed) = end_time_changed {
+            emit_cpi!(end_time_changed);
+        }
 
-        let metadata_ctx = CpiContext::new_with_signer(
-            ctx.accounts.token_metadata_program.to_account_info(),
-            metadata_accounts,
-            signer_seeds,
-        );
+        Ok(())
+    }
 
-        create_metadata_accounts_v3(
-            metadata_ctx,
-            data,
-            true, // is_mutable
-            true, // update_authority_is_signer
-            None,
-        )?;
+    /// Enables soulbound participation badges: a non-transferable Token-2022
+    /// mint from which every wallet receives one token on its first purchase.
+    pub fn init_participation_badge(ctx: Context<InitParticipationBadge>) -> Result<()> {
+        ctx.accounts
+            .raffle
+            .require_state(&[RaffleState::Draft, RaffleState::Open])?;
+        let raffle = &ctx.accounts.raffle;
 
-        // ✅ Create Master Edition using CPI
-        let edition_accounts = CreateMasterEditionV3 {
-            edition: ctx.accounts.master_edition.to_account_info(),
-            mint: ctx.accounts.prize_mint.to_account_info(),
-            update_authority: ctx.accounts.prize_mint.to_account_info(),
-            mint_authority: ctx.accounts.prize_mint.to_account_info(),
-            payer: ctx.accounts.winner.to_account_info(),
-            metadata: ctx.accounts.metadata.to_account_info(),
-            system_program: ctx.accounts.system_program.to_account_info(),
-            rent: ctx.accounts.rent.to_account_info(),
-            token_program: ctx.accounts.token_program.to_account_info(),
+        raffle.require_no_sales()?;
+
+        let raffle_key = raffle.key();
+        let badge_mint_seeds: &[&[u8]] =
+            &[b"badge_mint", raffle_key.as_ref(), &[ctx.bumps.badge_mint]];
+
+        let mint_len =
+            ExtensionType::try_calculate_account_len::<Token2022Mint>(&[ExtensionType::NonTransferable])?;
+
+        create_pda_account(
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.badge_mint.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            Rent::get()?.minimum_balance(mint_len),
+            mint_len as u64,
+            &ctx.accounts.token_program.key(),
+            badge_mint_seeds,
+        )?;
+
+        non_transferable_mint_initialize(CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            NonTransferableMintInitialize {
+                token_program_id: ctx.accounts.token_program.to_account_info(),
+                mint: ctx.accounts.badge_mint.to_account_info(),
+            },
+        ))?;
+
+        token_2022::initialize_mint2(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                InitializeMint2 {
+                    mint: ctx.accounts.badge_mint.to_account_info(),
+                },
+            ),
+            0,
+            &raffle_key,
+            None,
+        )?;
+
+        ctx.accounts.raffle.badge_mint = ctx.accounts.badge_mint.key();
+
+        Ok(())
+    }
+
+    /// Restricts ticket purchases to wallets in the Merkle tree with this root;
+    /// an all-zero root opens the raffle to everyone.
+    pub fn set_allowlist_root(ctx: Context<ConfigureRaffle>, allowlist_root: [u8; 32]) -> Result<()> {
+        ctx.accounts
+            .raffle
+            .require_state(&[RaffleState::Draft, RaffleState::Open])?;
+        ctx.accounts.raffle.allowlist_root = allowlist_root;
+
+        Ok(())
+    }
+
+    /// Restricts ticket purchases to holders of an NFT from this verified
+    /// Metaplex collection; `None` removes the requirement.
+    pub fn set_collection_gate(
+        ctx: Context<ConfigureRaffle>,
+        gate_collection: Option<Pubkey>,
+    ) -> Result<()> {
+        ctx.accounts
+            .raffle
+            .require_state(&[RaffleState::Draft, RaffleState::Open])?;
+        ctx.accounts.raffle.gate_collection = gate_collection;
+
+        Ok(())
+    }
+
+    /// Restricts ticket purchases to wallets holding at least `gate_min_amount`
+    /// base units of `gate_mint`; `None` removes the requirement.
+    pub fn set_token_gate(
+        ctx: Context<ConfigureRaffle>,
+        gate_mint: Option<Pubkey>,
+        gate_min_amount: u64,
+    ) -> Result<()> {
+        ctx.accounts
+            .raffle
+            .require_state(&[RaffleState::Draft, RaffleState::Open])?;
+        let raffle = &mut ctx.accounts.raffle;
+        raffle.gate_mint = gate_mint;
+        raffle.gate_min_amount = if gate_mint.is_some() {
+            gate_min_amount
+        } else {
+            0
+        };
+
+        Ok(())
+    }
+
+    /// Makes buyers pay by burning `price` base units of `burn_mint` per
+    /// ticket; `None` disables burn payments and drops any pricing schedule.
+    /// Locked once referrals are enabled, since the referral vault holds
+    /// `burn_mint`.
+    pub fn set_burn_payment(ctx: Context<ConfigureRaffle>, burn_mint: Option<Pubkey>) -> Result<()> {
+        ctx.accounts
+            .raffle
+            .require_state(&[RaffleState::Draft, RaffleState::Open])?;
+        let raffle = &mut ctx.accounts.raffle;
+
+        raffle.require_no_sales()?;
+
+        require!(
+            raffle.referral_vault == Pubkey::default(),
+            ErrorCode::BurnMintLockedByReferrals
+        );
+
+        raffle.burn_mint = burn_mint;
+        if burn_mint.is_none() {
+            raffle.pricing = PricingSchedule::default();
+        }
+
+        Ok(())
+    }
+
+    /// Makes entry require an Ed25519 signature from `entry_signer` over
+    /// `raffle || buyer || nonce || tickets` (integers little-endian u64),
+    /// verified by an Ed25519 program instruction placed right before the
+    /// purchase. Only free raffles can use it.
+    pub fn set_entry_signer(
+        ctx: Context<ConfigureRaffle>,
+        entry_signer: Option<Pubkey>,
+    ) -> Result<()> {
+        ctx.accounts
+            .raffle
+            .require_state(&[RaffleState::Draft, RaffleState::Open])?;
+        let raffle = &mut ctx.accounts.raffle;
+
+        require!(
+            entry_signer.is_none() || raffle.is_free(),
+            ErrorCode::EntrySignerRequiresFreeRaffle
+        );
+
+        raffle.entry_signer = entry_signer;
+
+        Ok(())
+    }
+
+    /// Limits every wallet to a single one-ticket entry, the usual setting for
+    /// free giveaways.
+    pub fn set_one_entry_per_wallet(
+        ctx: Context<ConfigureRaffle>,
+        one_entry_per_wallet: bool,
+    ) -> Result<()> {
+        ctx.accounts
+            .raffle
+            .require_state(&[RaffleState::Draft, RaffleState::Open])?;
+        let raffle = &mut ctx.accounts.raffle;
+
+        raffle.require_no_sales()?;
+
+        raffle.one_entry_per_wallet = one_entry_per_wallet;
+
+        Ok(())
+    }
+
+    /// Enables referral rewards: `referral_bps` of every burn payment made
+    /// through a registered referrer is held in the raffle's referral vault
+    /// instead of being burned. Referrals only work with burn payments, so
+    /// `set_burn_payment` must be called first; the burn mint cannot change
+    /// afterwards.
+    pub fn init_referrals(ctx: Context<InitReferrals>, referral_bps: u16) -> Result<()> {
+        ctx.accounts
+            .raffle
+            .require_state(&[RaffleState::Draft, RaffleState::Open])?;
+        let raffle = &mut ctx.accounts.raffle;
+
+        raffle.require_no_sales()?;
+        require!(
+            referral_bps <= MAX_BASIS_POINTS,
+            ErrorCode::InvalidReferralBasisPoints
+        );
+
+        raffle.referral_bps = referral_bps;
+        raffle.referral_vault = ctx.accounts.referral_vault.key();
+
+        Ok(())
+    }
+
+    /// Creates the caller's referral account for a raffle; buyers pass it to
+    /// attribute their purchases.
+    pub fn register_referrer(ctx: Context<RegisterReferrer>) -> Result<()> {
+        ctx.accounts
+            .raffle
+            .require_state(&[RaffleState::Draft, RaffleState::Open])?;
+        let referral = &mut ctx.accounts.referral;
+        referral.raffle = ctx.accounts.raffle.key();
+        referral.referrer = ctx.accounts.referrer.key();
+        referral.tickets_referred = 0;
+        referral.earned = 0;
+        referral.claimed = 0;
+        referral.bump = ctx.bumps.referral;
+
+        Ok(())
+    }
+
+    /// Pays out the referrer's unclaimed rewards from the referral vault.
+    pub fn claim_referral_rewards(ctx: Context<ClaimReferralRewards>) -> Result<()> {
+        let raffle = &ctx.accounts.raffle;
+        let referral = &ctx.accounts.referral;
+
+        let unclaimed = referral
+            .earned
+            .checked_sub(referral.claimed)
+            .ok_or(ErrorCode::Overflow)?;
+        require!(unclaimed > 0, ErrorCode::NoReferralRewards);
+
+        let creator = raffle.creator;
+        let raffle_id = raffle.raffle_id.to_le_bytes();
+        let raffle_seeds: &[&[u8]] = &[b"raffle", creator.as_ref(), &raffle_id, &[raffle.bump]];
+
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token_interface::TransferChecked {
+                    from: ctx.accounts.referral_vault.to_account_info(),
+                    mint: ctx.accounts.burn_mint.to_account_info(),
+                    to: ctx.accounts.referrer_token_account.to_account_info(),
+                    authority: raffle.to_account_info(),
+                },
+                &[raffle_seeds],
+            ),
+            unclaimed,
+            ctx.accounts.burn_mint.decimals,
+        )?;
+
+        ctx.accounts.referral.claimed = ctx.accounts.referral.earned;
+
+        Ok(())
+    }
+
+    /// Replaces the flat `price` with a pricing schedule, charged through burn
+    /// payments, which must already be enabled.
+    pub fn set_pricing(ctx: Context<ConfigureRaffle>, pricing: PricingSchedule) -> Result<()> {
+        ctx.accounts
+            .raffle
+            .require_state(&[RaffleState::Draft, RaffleState::Open])?;
+        let raffle = &mut ctx.accounts.raffle;
+
+        raffle.require_no_sales()?;
+        require!(
+            raffle.burn_mint.is_some(),
+            ErrorCode::PricingRequiresBurnPayment
+        );
+        pricing.validate()?;
+
+        raffle.pricing = pricing;
+        require!(
+            raffle.entry_signer.is_none() || raffle.is_free(),
+            ErrorCode::EntrySignerRequiresFreeRaffle
+        );
+
+        Ok(())
+    }
+
+    /// Edits the basic raffle parameters while it is a draft or has not sold
+    /// any tickets; `None` fields are left unchanged. `end_time` is only moved
+    /// by `extend_raffle`.
+    pub fn update_raffle(ctx: Context<ConfigureRaffle>, update: RaffleUpdate) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+
+        raffle.require_state(&[RaffleState::Draft, RaffleState::Open])?;
+        raffle.require_no_sales()?;
+
+        if let Some(name) = update.name {
+            require!(name.len() <= MAX_NAME_LENGTH, ErrorCode::NameTooLong);
+            raffle.name = name;
+        }
+        if let Some(start_time) = update.start_time {
+            raffle.start_time = start_time;
+        }
+        if let Some(price) = update.price {
+            raffle.price = price;
+            require!(
+                raffle.entry_signer.is_none() || raffle.is_free(),
+                ErrorCode::EntrySignerRequiresFreeRaffle
+            );
+        }
+        if let Some(max_tickets) = update.max_tickets {
+            // The compressed ticket tree is sized for the current maximum
+            require!(
+                raffle.ticket_mode != TicketMode::Compressed || max_tickets <= raffle.max_tickets,
+                ErrorCode::TreeTooSmall
+            );
+            raffle.nft_config.validate(max_tickets)?;
+            raffle.max_tickets = max_tickets;
+        }
+
+        Ok(())
+    }
+
+    /// Starts ticket sales for a draft raffle. NFT and compressed raffles need
+    /// their collection from `init_raffle` first.
+    pub fn open_raffle(ctx: Context<ConfigureRaffle>) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+
+        raffle.require_state(&[RaffleState::Draft])?;
+        raffle.require_collection()?;
+
+        raffle.state = RaffleState::Open;
+
+        Ok(())
+    }
+
+    /// Configures `extend_raffle` and anti-snipe auto-extension.
+    pub fn set_extension_policy(
+        ctx: Context<ConfigureRaffle>,
+        extension_policy: ExtensionPolicy,
+    ) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+
+        raffle.require_state(&[RaffleState::Draft, RaffleState::Open])?;
+        raffle.require_no_sales()?;
+        require!(
+            extension_policy.anti_snipe_window >= 0 && extension_policy.anti_snipe_extension >= 0,
+            ErrorCode::InvalidEndTime
+        );
+
+        raffle.extension_policy = extension_policy;
+
+        Ok(())
+    }
+
+    /// Moves `end_time` while the sale is still running, within the limits of
+    /// `Raffle::change_end_time`.
+    pub fn extend_raffle(ctx: Context<ExtendRaffle>, new_end_time: i64) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+
+        raffle.require_state(&[RaffleState::Draft, RaffleState::Open])?;
+        let end_time_changed =
+            raffle.change_end_time(new_end_time, Clock::get()?.unix_timestamp)?;
+        emit_cpi!(end_time_changed);
+
+        Ok(())
+    }
+
+    /// First step of an authority transfer; `None` withdraws a pending proposal.
+    pub fn propose_authority(
+        ctx: Context<ConfigureRaffle>,
+        new_authority: Option<Pubkey>,
+    ) -> Result<()> {
+        ctx.accounts.raffle.pending_authority = new_authority;
+
+        Ok(())
+    }
+
+    /// Completes an authority transfer, signed by the proposed authority.
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+        raffle.authority = ctx.accounts.new_authority.key();
+        raffle.pending_authority = None;
+
+        Ok(())
+    }
+
+    /// Sets the operator allowed to run the draw; `None` removes it. Operators
+    /// can only commit Switchboard randomness, never mock randomness.
+    pub fn set_operator(ctx: Context<ConfigureRaffle>, operator: Option<Pubkey>) -> Result<()> {
+        ctx.accounts.raffle.operator = operator;
+
+        Ok(())
+    }
+
+    /// Creates the protocol-wide config, administered by the program's upgrade
+    /// authority, and the raffle registry.
+    pub fn init_protocol_config(ctx: Context<InitProtocolConfig>) -> Result<()> {
+        let protocol_config = &mut ctx.accounts.protocol_config;
+        protocol_config.admin = ctx.accounts.admin.key();
+        protocol_config.paused = false;
+        protocol_config.bump = ctx.bumps.protocol_config;
+
+        let raffle_registry = &mut ctx.accounts.raffle_registry;
+        raffle_registry.next_raffle_id = 0;
+        raffle_registry.bump = ctx.bumps.raffle_registry;
+
+        Ok(())
+    }
+
+    /// Global circuit breaker: while paused no raffle can be created, sell
+    /// tickets or run its draw. Claims stay available.
+    pub fn set_protocol_paused(ctx: Context<SetProtocolPaused>, paused: bool) -> Result<()> {
+        ctx.accounts.protocol_config.paused = paused;
+
+        Ok(())
+    }
+
+    /// Pauses ticket sales and the draw for a single raffle.
+    pub fn pause_raffle(ctx: Context<ConfigureRaffle>, paused: bool) -> Result<()> {
+        ctx.accounts.raffle.paused = paused;
+
+        Ok(())
+    }
+
+    /// Cancels a raffle with nothing to draw from: no tickets sold, or a
+    /// fungible raffle whose registration closed with no tickets registered.
+    pub fn cancel_raffle(ctx: Context<CancelRaffle>) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+
+        raffle.require_state(&[RaffleState::Draft, RaffleState::Open, RaffleState::Closed])?;
+        require!(
+            raffle.drawable_tickets() == 0
+                && raffle.registration_closed(Clock::get()?.unix_timestamp),
+            ErrorCode::TicketsAlreadySold
+        );
+
+        raffle.state = RaffleState::Cancelled;
+
+        emit_cpi!(RaffleCancelled {
+            raffle_id: raffle.raffle_id,
+        });
+
+        Ok(())
+    }
+
+    /// Rewrites a raffle created before `RaffleState` into the current layout,
+    /// deriving its state from the old lifecycle flags.
+    pub fn migrate_raffle(ctx: Context<MigrateRaffle>) -> Result<()> {
+        let raffle_info = ctx.accounts.raffle.to_account_info();
+
+        let legacy = {
+            let data = raffle_info.try_borrow_data()?;
+            require!(
+                data.len() == 8 + LegacyRaffle::INIT_SPACE && data[..8] == *Raffle::DISCRIMINATOR,
+                ErrorCode::AlreadyMigrated
+            );
+            LegacyRaffle::deserialize(&mut &data[8..])?
         };
+        require_keys_eq!(
+            legacy.authority,
+            ctx.accounts.authority.key(),
+            ErrorCode::NotAuthorized
+        );
+
+        let collection_initialized = ctx.accounts.collection_mint.owner != &system_program::ID;
+        let raffle = legacy.into_raffle(collection_initialized);
+        let space = 8 + Raffle::INIT_SPACE;
+        let shortfall = Rent::get()?
+            .minimum_balance(space)
+            .saturating_sub(raffle_info.lamports());
+        if shortfall > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.payer.to_account_info(),
+                        to: raffle_info.clone(),
+                    },
+                ),
+                shortfall,
+            )?;
+        }
+        raffle_info.resize(space)?;
+        raffle.try_serialize(&mut &mut raffle_info.try_borrow_mut_data()?[..])?;
+
+        Ok(())
+    }
+
+    /// Permissionless crank freezing a fungible ticket account once the sale
+    /// has closed, so its tickets cannot move before they are registered for
+    /// the draw. Freezing is not atomic with `end_time`: an account stays
+    /// transferable until someone cranks it, and only the balance at
+    /// registration counts. `thaw_tickets` releases accounts after the draw.
+    pub fn freeze_tickets(ctx: Context<FreezeTickets>) -> Result<()> {
+        let raffle = &ctx.accounts.raffle;
+
+        require!(
+            raffle.ticket_mode == TicketMode::Fungible,
+            ErrorCode::InvalidTicketMode
+        );
+        require!(raffle.freeze_after_sale, ErrorCode::FreezeNotEnabled);
+        raffle.require_state(&[RaffleState::Closed])?;
+
+        let creator = raffle.creator;
+        let raffle_id = raffle.raffle_id.to_le_bytes();
+        let raffle_seeds: &[&[u8]] = &[b"raffle", creator.as_ref(), &raffle_id, &[raffle.bump]];
+
+        freeze_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            FreezeAccount {
+                account: ctx.accounts.ticket_account.to_account_info(),
+                mint: ctx.accounts.ticket_mint.to_account_info(),
+                authority: raffle.to_account_info(),
+            },
+            &[raffle_seeds],
+        ))?;
+
+        Ok(())
+    }
+
+    /// Permissionless crank thawing a fungible ticket account frozen by
+    /// `freeze_tickets` once registration is over, so unregistered tickets do
+    /// not stay locked forever.
+    pub fn thaw_tickets(ctx: Context<FreezeTickets>) -> Result<()> {
+        let raffle = &ctx.accounts.raffle;
+
+        require!(
+            raffle.ticket_mode == TicketMode::Fungible,
+            ErrorCode::InvalidTicketMode
+        );
+        raffle.require_state(&[
+            RaffleState::RandomnessRequested,
+            RaffleState::Drawn,
+            RaffleState::Settled,
+            RaffleState::Cancelled,
+        ])?;
+
+        let creator = raffle.creator;
+        let raffle_id = raffle.raffle_id.to_le_bytes();
+        let raffle_seeds: &[&[u8]] = &[b"raffle", creator.as_ref(), &raffle_id, &[raffle.bump]];
+
+        thaw_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            ThawAccount {
+                account: ctx.accounts.ticket_account.to_account_info(),
+                mint: ctx.accounts.ticket_mint.to_account_info(),
+                authority: raffle.to_account_info(),
+            },
+            &[raffle_seeds],
+        ))?;
+
+        Ok(())
+    }
+
+pub fn commit_randomness(ctx: Context<CommitRandomness>, use_mock: bool) -> Result<()> {
+    let raffle = &mut ctx.accounts.raffle;
+    let clock = Clock::get()?;
+
+    require!(
+        raffle.can_draw(ctx.accounts.authority.key()),
+        ErrorCode::NotAuthorized
+    );
+
+    raffle.require_state(&[RaffleState::Closed])?;
+    check_not_paused(&ctx.accounts.protocol_config, raffle)?;
+    require!(
+        raffle.registration_closed(clock.unix_timestamp),
+        ErrorCode::RegistrationOpen
+    );
+    require!(raffle.drawable_tickets() > 0, ErrorCode::NoDrawableTickets);
+
+    if use_mock {
+        // Mock randomness is predictable, so only the authority may use it
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            raffle.authority,
+            ErrorCode::NotAuthorized
+        );
+
+        // ✅ Deterministic mock randomness
+        let combined_value = (clock.unix_timestamp as u128)
+            .wrapping_mul(raffle.raffle_id as u128)
+            .wrapping_add(clock.slot as u128);
+
+        let randomness_bytes = combined_value.to_le_bytes();
+        raffle.randomness[..16].copy_from_slice(&randomness_bytes);
+        raffle.randomness_account = None;
+
+        raffle.state = RaffleState::RandomnessRequested;
+
+
+        msg!("Mock randomness committed: {:?}", raffle.randomness);
+    } else {
+        // ✅ Ensure randomness account exists
+        let randomness_ai = ctx
+            .accounts
+            .randomness_data_account
+            .as_ref()
+            .ok_or(ErrorCode::MissingRandomnessAccount)?;
+        require!(
+            *randomness_ai.owner == ON_DEMAND_MAINNET_PID
+                || *randomness_ai.owner == ON_DEMAND_DEVNET_PID,
+            ErrorCode::InvalidRandomnessAccount
+        );
+
+        let randomness_data = RandomnessAccountData::parse(randomness_ai.try_borrow_data()?)
+            .map_err(|_| ErrorCode::InvalidRandomnessAccount)?;
+        // Only randomness requested in the previous slot is still unknown, so
+        // the committer cannot pick a value that has already been revealed
+        require!(
+            randomness_data.seed_slot == clock.slot.saturating_sub(1),
+            ErrorCode::RandomnessExpired
+        );
+
+        raffle.randomness = [0u8; 32];
+        raffle.randomness_account = Some(randomness_ai.key());
+
+        raffle.state = RaffleState::RandomnessRequested;
+
+        msg!("Real randomness committed: {}", randomness_ai.key());
+    }
+
+    let randomness_account = if use_mock {
+        None
+    } else {
+        ctx.accounts
+            .randomness_data_account
+            .as_ref()
+            .map(|account| account.key())
+    };
+    emit_cpi!(RandomnessCommitted {
+        raffle_id: raffle.raffle_id,
+        randomness_account,
+        slot: clock.slot,
+    });
+
+    Ok(())
+}
+
+    pub fn reveal_winner(ctx: Context<RevealWinner>) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+
+    require!(
+        raffle.can_draw(ctx.accounts.authority.key()),
+        ErrorCode::NotAuthorized
+    );
+
+    raffle.require_state(&[RaffleState::RandomnessRequested])?;
+    check_not_paused(&ctx.accounts.protocol_config, raffle)?;
+
+        require!(
+            raffle.total_num_tickets_bought > 0,
+            ErrorCode::NoTicketsBought
+        );
+        if let Some(randomness_account) = raffle.randomness_account {
+            let randomness_ai = ctx
+                .accounts
+                .randomness_data_account
+                .as_ref()
+                .ok_or(ErrorCode::MissingRandomnessAccount)?;
+            require_keys_eq!(
+                randomness_ai.key(),
+                randomness_account,
+                ErrorCode::InvalidRandomnessAccount
+            );
+
+            let randomness_data = RandomnessAccountData::parse(randomness_ai.try_borrow_data()?)
+                .map_err(|_| ErrorCode::InvalidRandomnessAccount)?;
+            raffle.randomness = randomness_data
+                .get_value(&Clock::get()?)
+                .map_err(|_| ErrorCode::RandomnessNotResolved)?;
+        }
+        require!(
+            raffle.randomness != [0u8; 32],
+            ErrorCode::RandomnessNotCommitted
+        );
+        if matches!(raffle.ticket_mode, TicketMode::Nft | TicketMode::Token2022) {
+            require!(
+                raffle.ticket_numbers.len() == raffle.total_num_tickets_bought as usize,
+                ErrorCode::InvalidTicketData
+            );
+        }
+
+        let drawable_tickets = raffle.drawable_tickets();
+        require!(drawable_tickets > 0, ErrorCode::NoTicketsBought);
+
+        // Convert first 16 bytes of randomness to u128
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(&raffle.randomness[..16]);
+        let random_value = u128::from_le_bytes(bytes);
+
+        // Winner index
+        let winner_index = (random_value % drawable_tickets as u128) as usize;
+
+        // Compressed and fungible ticket owners are only known off-chain; the
+        // winner is set when the holder of the winning ticket claims
+        let winner_pubkey = match raffle.ticket_mode {
+            TicketMode::Nft | TicketMode::Token2022 => raffle.ticket_numbers[winner_index],
+            TicketMode::Compressed | TicketMode::Fungible => Pubkey::default(),
+        };
+
+        if matches!(raffle.ticket_mode, TicketMode::Nft | TicketMode::Token2022) {
+            let raffle_key = raffle.key();
+            let (winning_ticket_mint, _) = Pubkey::find_program_address(
+                &[
+                    b"ticket_mint",
+                    raffle_key.as_ref(),
+                    &(winner_index as u64).to_le_bytes(),
+                ],
+                &crate::ID,
+            );
+            raffle.winning_ticket_mint = winning_ticket_mint;
+        }
+
+        // Set winner details
+        raffle.winner = winner_pubkey;
+        raffle.winner_index = Some(winner_index as u64);
+        raffle.state = RaffleState::Drawn;
+
+        emit_cpi!(WinnerChosen {
+            raffle_id: raffle.raffle_id,
+            winner: winner_pubkey,
+            winner_index: winner_index as u64,
+        });
+
+        Ok(())
+    }
+
+    pub fn claim_prize<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClaimPrize<'info>>,
+        ticket_leaf: Option<TicketLeaf>,
+    ) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+
+        raffle.require_state(&[RaffleState::Drawn])?;
+
+        require!(
+raffle.randomness != [0u8; 32],
+ErrorCode::RandomnessNotCommitted
+);
+        // ✅ Check winner conditions here...
+        match raffle.ticket_mode {
+            TicketMode::Nft | TicketMode::Token2022 if raffle.winner_by_holder => {
+                // Whoever holds the winning ticket claims it, burning the ticket
+                let ticket_mint = ctx
+                    .accounts
+                    .winning_ticket_mint
+                    .as_ref()
+                    .ok_or(ErrorCode::MissingWinningTicket)?;
+                let ticket_account = ctx
+                    .accounts
+                    .winning_ticket_account
+                    .as_ref()
+                    .ok_or(ErrorCode::MissingWinningTicket)?;
+                require_keys_eq!(
+                    ticket_mint.key(),
+                    raffle.winning_ticket_mint,
+                    ErrorCode::InvalidNFT
+                );
+                let ticket_token_program = ctx
+                    .accounts
+                    .ticket_token_program
+                    .as_ref()
+                    .ok_or(ErrorCode::MissingWinningTicket)?;
+                require!(ticket_account.amount == 1, ErrorCode::NotWinner);
+
+                token_interface::burn(
+                    CpiContext::new(
+                        ticket_token_program.to_account_info(),
+                        token_interface::Burn {
+                            mint: ticket_mint.to_account_info(),
+                            from: ticket_account.to_account_info(),
+                            authority: ctx.accounts.winner.to_account_info(),
+                        },
+                    ),
+                    1,
+                )?;
+
+                raffle.winner = ctx.accounts.winner.key();
+            }
+            TicketMode::Nft | TicketMode::Token2022 => {
+                require!(
+                    raffle.winner == ctx.accounts.winner.key(),
+                    ErrorCode::NotWinner
+                );
+            }
+            TicketMode::Compressed => {
+                let ticket_leaf = ticket_leaf.ok_or(ErrorCode::MissingLeafProof)?;
+                let merkle_tree = ctx
+                    .accounts
+                    .merkle_tree
+                    .as_ref()
+                    .ok_or(ErrorCode::MissingLeafProof)?;
+                let compression_program = ctx
+                    .accounts
+                    .compression_program
+                    .as_ref()
+                    .ok_or(ErrorCode::MissingLeafProof)?;
+                require_keys_eq!(
+                    merkle_tree.key(),
+                    raffle.merkle_tree,
+                    ErrorCode::InvalidMerkleTree
+                );
+
+                let winner_index = raffle.winner_index.ok_or(ErrorCode::WinnerNotChosen)?;
+                verify_ticket_leaf(
+                    compression_program,
+                    merkle_tree,
+                    ctx.remaining_accounts,
+                    &ticket_leaf,
+                    ctx.accounts.winner.key(),
+                    winner_index,
+                )?;
+
+                raffle.winner = ctx.accounts.winner.key();
+            }
+            TicketMode::Fungible => {
+                let snapshot = ctx
+                    .accounts
+                    .ticket_snapshot
+                    .as_ref()
+                    .ok_or(ErrorCode::MissingTicketSnapshot)?;
+                let winner_index = raffle.winner_index.ok_or(ErrorCode::WinnerNotChosen)?;
+
+                require_keys_eq!(snapshot.raffle, raffle.key(), ErrorCode::NotWinner);
+                require_keys_eq!(
+                    snapshot.owner,
+                    ctx.accounts.winner.key(),
+                    ErrorCode::NotWinner
+                );
+                require!(snapshot.contains(winner_index), ErrorCode::NotWinner);
+
+                raffle.winner = ctx.accounts.winner.key();
+            }
+        }
+
+
+
+
+        // ✅ Mint 1 token to winner
+        let cpi_accounts = MintTo {
+            mint: ctx.accounts.prize_mint.to_account_info(),
+            to: ctx.accounts.winner_ata.to_account_info(),
+            authority: ctx.accounts.prize_mint.to_account_info(), // PDA as authority
+        };
+
+        let binding = raffle.key();
+        let seeds: &[&[u8]] = &[b"prize_mint", binding.as_ref(), &[ctx.bumps.prize_mint]];
+        let signer_seeds = &[seeds];
+
+        mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts,
+                signer_seeds,
+            ),
+            1,
+        )?;
+
+        // ✅ Create Metadata using CPI
+        // The prize update authority is the prize mint, so the mint authority
+        // PDA is listed unverified here and signs below
+        let data = DataV2 {
+            seller_fee_basis_points: raffle.royalty.seller_fee_basis_points,
+            creators: Some(raffle.metaplex_creators(ctx.accounts.mint_authority.key(), false)),
+            collection: None,
+            uses: None,
+            name: raffle.name.clone(),
+            symbol: raffle.nft_config.symbol.clone(),
+            uri: raffle.nft_config.prize_uri.clone(),
+        };
+
+        let metadata_accounts = CreateMetadataAccountsV3 {
+            metadata: ctx.accounts.metadata.to_account_info(),
+            mint: ctx.accounts.prize_mint.to_account_info(),
+            mint_authority: ctx.accounts.prize_mint.to_account_info(), // PDA as mint authority
+            payer: ctx.accounts.winner.to_account_info(),
+            update_authority: ctx.accounts.prize_mint.to_account_info(), // PDA as update authority
+            system_program: ctx.accounts.system_program.to_account_info(),
+            rent: ctx.accounts.rent.to_account_info(),
+        };
+
+        let metadata_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_metadata_program.to_account_info(),
+            metadata_accounts,
+            signer_seeds,
+        );
+
+        create_metadata_accounts_v3(
+            metadata_ctx,
+            data,
+            true, // is_mutable
+            true, // update_authority_is_signer
+            None,
+        )?;
+
+        // ✅ Create Master Edition using CPI
+        let edition_accounts = CreateMasterEditionV3 {
+            edition: ctx.accounts.master_edition.to_account_info(),
+            mint: ctx.accounts.prize_mint.to_account_info(),
+            update_authority: ctx.accounts.prize_mint.to_account_info(),
+            mint_authority: ctx.accounts.prize_mint.to_account_info(),
+            payer: ctx.accounts.winner.to_account_info(),
+            metadata: ctx.accounts.metadata.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            rent: ctx.accounts.rent.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+        };
+
+        let edition_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_metadata_program.to_account_info(),
+            edition_accounts,
+            signer_seeds,
+        );
+
+        create_master_edition_v3(edition_ctx, Some(0))?;
+
+        let mint_authority_seeds: &[&[u8]] = &[
+            b"mint_authority",
+            binding.as_ref(),
+            &[ctx.bumps.mint_authority],
+        ];
+        sign_metadata(CpiContext::new_with_signer(
+            ctx.accounts.token_metadata_program.to_account_info(),
+            SignMetadata {
+                creator: ctx.accounts.mint_authority.to_account_info(),
+                metadata: ctx.accounts.metadata.to_account_info(),
+            },
+            &[mint_authority_seeds],
+        ))?;
+
+        raffle.state = RaffleState::Settled;
+
+        emit_cpi!(PrizeClaimed {
+            raffle_id: raffle.raffle_id,
+            winner: ctx.accounts.winner.key(),
+            prize_mint: ctx.accounts.prize_mint.key(),
+        });
+
+        Ok(())
+    }
+}
+#[event]
+pub struct WinnerChosen {
+    pub raffle_id: u64,
+    pub winner: Pubkey,
+    pub winner_index: u64,
+}
+
+#[event]
+pub struct EndTimeChanged {
+    pub raffle_id: u64,
+    pub old_end_time: i64,
+    pub new_end_time: i64,
+    /// Pushed automatically by a purchase inside the anti-snipe window
+    pub anti_snipe: bool,
+}
+
+#[event]
+pub struct TicketsPurchased {
+    pub raffle_id: u64,
+    pub buyer: Pubkey,
+    /// Tickets `first_ticket_index..first_ticket_index + tickets` were bought
+    pub first_ticket_index: u64,
+    pub tickets: u64,
+    /// `burn_mint` units charged, 0 for raffles without burn payments
+    pub price_paid: u64,
+}
+
+#[event]
+pub struct RaffleCreated {
+    pub raffle_id: u64,
+    pub raffle: Pubkey,
+    pub authority: Pubkey,
+    pub start_time: u64,
+    pub end_time: i64,
+    pub price: u64,
+    pub max_tickets: u64,
+}
+
+#[event]
+pub struct CollectionInitialized {
+    pub raffle_id: u64,
+    pub collection_mint: Pubkey,
+}
+
+#[event]
+pub struct RandomnessCommitted {
+    pub raffle_id: u64,
+    /// Switchboard randomness account, `None` for mock randomness
+    pub randomness_account: Option<Pubkey>,
+    pub slot: u64,
+}
+
+#[event]
+pub struct PrizeClaimed {
+    pub raffle_id: u64,
+    pub winner: Pubkey,
+    pub prize_mint: Pubkey,
+}
+
+#[event]
+pub struct RaffleCancelled {
+    pub raffle_id: u64,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RevealWinner<'info> {
+    /// Raffle authority or operator
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+    seeds = [b"raffle", raffle.creator.as_ref(), &raffle.raffle_id.to_le_bytes()],
+bump = raffle.bump,
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    /// CHECK: Switchboard randomness only; checked against
+    /// `raffle.randomness_account`
+    pub randomness_data_account: Option<UncheckedAccount<'info>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimPrize<'info> {
+    #[account(mut)]
+    pub raffle: Account<'info, Raffle>,
+
+    #[account(mut)]
+    pub winner: Signer<'info>,
+
+    /// CHECK: PDA is only used as a signer to verify itself as prize creator
+    #[account(
+        seeds = [b"mint_authority", raffle.key().as_ref()],
+        bump
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = winner,
+        seeds = [b"prize_mint", raffle.key().as_ref()],
+        bump,
+        mint::decimals = 0,
+        mint::authority = prize_mint,
+        mint::freeze_authority = prize_mint
+    )]
+    pub prize_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = winner,
+        associated_token::mint = prize_mint,
+        associated_token::authority = winner
+    )]
+    pub winner_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Metaplex Metadata Program
+        #[account(
+        mut,
+        seeds = [
+            b"metadata",
+            token_metadata_program.key().as_ref(),
+            prize_mint.key().as_ref()
+        ],
+        bump,
+        seeds::program = token_metadata_program.key()
+    )]
+    pub metadata: UncheckedAccount<'info>,
+    /// CHECK: Master Edition Account
+        #[account(
+        mut,
+        seeds = [
+            b"metadata",
+            token_metadata_program.key().as_ref(),
+            prize_mint.key().as_ref(),
+            b"edition"
+        ],
+        bump,
+        seeds::program = token_metadata_program.key()
+    )]
+    pub master_edition: UncheckedAccount<'info>,
+
+    pub token_metadata_program: Program<'info, Metadata>,
+
+    /// NFT mode with `winner_by_holder` only; checked against `raffle.winning_ticket_mint`
+    #[account(mut)]
+    pub winning_ticket_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// NFT mode with `winner_by_holder` only; the claimant's winning ticket, burned on claim
+    #[account(
+        mut,
+        token::mint = winning_ticket_mint,
+        token::authority = winner,
+        token::token_program = ticket_token_program,
+    )]
+    pub winning_ticket_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// NFT mode with `winner_by_holder` only; SPL Token or Token-2022, matching the ticket
+    pub ticket_token_program: Option<Interface<'info, TokenInterface>>,
+
+    /// Fungible mode only; the claimant's registered ticket range
+    pub ticket_snapshot: Option<Account<'info, TicketSnapshot>>,
+
+    /// CHECK: compressed mode only; checked against `raffle.merkle_tree`
+    pub merkle_tree: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: compressed mode only
+    #[account(address = SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: Option<UncheckedAccount<'info>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct InitConfig<'info> {
+    /// Raffle authority; may be a PDA signer such as a multisig vault
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(mut, seeds = [b"raffle_registry"], bump = raffle_registry.bump)]
+    pub raffle_registry: Account<'info, RaffleRegistry>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Raffle::INIT_SPACE,
+    seeds = [b"raffle", authority.key().as_ref(), &raffle_registry.next_raffle_id.to_le_bytes()],
+
+        bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + RaffleIndex::INIT_SPACE,
+        seeds = [b"raffle_index".as_ref(), &raffle_registry.next_raffle_id.to_le_bytes()],
+        bump
+    )]
+    pub raffle_index: Account<'info, RaffleIndex>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + AuthorityRaffles::INIT_SPACE,
+        seeds = [b"authority_raffles", authority.key().as_ref()],
+        bump
+    )]
+    pub authority_raffles: Account<'info, AuthorityRaffles>,
+
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct InitRaffle<'info> {
+    pub authority: Signer<'info>,
+
+    /// Payer for all inits
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+    seeds = [b"raffle", raffle.creator.as_ref(), &raffle.raffle_id.to_le_bytes()],
+
+        bump = raffle.bump,
+        has_one = authority @ ErrorCode::NotAuthorized,
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    /// PDA used as mint authority (separate from the mint being created)
+    #[account(
+        seeds = [b"mint_authority", raffle.key().as_ref()],
+        bump
+    )]
+    /// CHECK: PDA is only used as a signer for CPIs (mint + metadata); no data read/written
+    pub mint_authority: UncheckedAccount<'info>,
+
+    /// The collection mint we’re creating (PDA mint)
+    #[account(
+        init_if_needed,
+        payer = payer,
+        seeds = [b"collection_mint", raffle.key().as_ref()],
+        bump,
+        mint::decimals = 0,
+        mint::authority = mint_authority,        // ✅ not self
+        mint::freeze_authority = mint_authority, // ✅ not self
+        mint::token_program = token_program
+    )]
+    pub collection_mint: InterfaceAccount<'info, Mint>,
+
+    /// ATA to hold the minted supply (owned by the PDA authority)
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = collection_mint,
+        associated_token::authority = mint_authority,
+        associated_token::token_program = token_program
+    )]
+    pub collection_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Metaplex Metadata PDA (derived off the Metaplex program)
+    #[account(
+        mut,
+        seeds = [
+            b"metadata",
+            token_metadata_program.key().as_ref(),
+            collection_mint.key().as_ref()
+        ],
+        bump,
+        seeds::program = token_metadata_program.key()
+    )]
+    /// CHECK: created & verified by Metaplex CPI
+    pub metadata: UncheckedAccount<'info>,
+
+    /// Metaplex Master Edition PDA
+    #[account(
+        mut,
+        seeds = [
+            b"metadata",
+            token_metadata_program.key().as_ref(),
+            collection_mint.key().as_ref(),
+            b"edition"
+        ],
+        bump,
+        seeds::program = token_metadata_program.key()
+    )]
+    /// CHECK: created & verified by Metaplex CPI
+    pub master_edition: UncheckedAccount<'info>,
+
+    // Programs
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_metadata_program: Program<'info, Metadata>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(allowlist_proof: Option<AllowlistProof>, entry_nonce: Option<u64>)]
+pub struct BuyTickets<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"raffle", raffle.creator.as_ref(), &raffle.raffle_id.to_le_bytes()],
+        bump = raffle.bump,
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    /// CHECK: PDA is only used as a signer for CPIs (ticket metadata + collection verification)
+    #[account(
+        seeds = [b"mint_authority", raffle.key().as_ref()],
+        bump
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        seeds = [ b"ticket_mint",
+            raffle.key().as_ref(), raffle.total_num_tickets_bought.to_le_bytes().as_ref()],
+        bump,
+        mint::decimals = 0,
+        mint::authority = raffle,
+        mint::freeze_authority = raffle,
+        mint::token_program = token_program,
+    )]
+    pub ticket_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = ticket_mint,
+        associated_token::authority = payer,
+        associated_token::token_program = token_program,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"collection_mint".as_ref(), raffle.key().as_ref()],
+        bump
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: created by Metaplex CPI
+    #[account(
+        mut,
+        seeds = [
+            b"metadata",
+            token_metadata_program.key().as_ref(),
+            ticket_mint.key().as_ref()
+        ],
+        bump,
+        seeds::program = token_metadata_program.key()
+    )]
+    pub ticket_metadata: UncheckedAccount<'info>,
+
+    /// CHECK: created by Metaplex CPI
+    #[account(
+        mut,
+        seeds = [
+            b"metadata",
+            token_metadata_program.key().as_ref(),
+            ticket_mint.key().as_ref(),
+            b"edition"
+        ],
+        bump,
+        seeds::program = token_metadata_program.key()
+    )]
+    pub ticket_master_edition: UncheckedAccount<'info>,
+
+    /// CHECK: validated by Metaplex during collection verification
+    #[account(
+        mut,
+        seeds = [
+            b"metadata",
+            token_metadata_program.key().as_ref(),
+            mint.key().as_ref()
+        ],
+        bump,
+        seeds::program = token_metadata_program.key()
+    )]
+    pub collection_metadata: UncheckedAccount<'info>,
+
+    /// CHECK: validated by Metaplex during collection verification
+    #[account(
+        seeds = [
+            b"metadata",
+            token_metadata_program.key().as_ref(),
+            mint.key().as_ref(),
+            b"edition"
+        ],
+        bump,
+        seeds::program = token_metadata_program.key()
+    )]
+    pub collection_master_edition: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + Participant::INIT_SPACE,
+        seeds = [b"participant", raffle.key().as_ref(), payer.key().as_ref()],
+        bump
+    )]
+    pub participant: Account<'info, Participant>,
+
+    pub badge: BadgeAccounts<'info>,
+
+    pub gates: GateAccounts<'info>,
+
+    pub payment: PaymentAccounts<'info>,
+
+    pub entry: EntryAccounts<'info>,
+
+    /// Marks `entry_nonce` as used; only passed for signer-gated raffles
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + EntryNonce::INIT_SPACE,
+        seeds = [
+            b"entry_nonce",
+            raffle.key().as_ref(),
+            payer.key().as_ref(),
+            &entry_nonce.unwrap_or_default().to_le_bytes(),
+        ],
+        bump
+    )]
+    pub entry_record: Option<Account<'info, EntryNonce>>,
+
+    pub referral: ReferralAccounts<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_metadata_program: Program<'info, Metadata>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct InitTicketTree<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"raffle", raffle.creator.as_ref(), &raffle.raffle_id.to_le_bytes()],
+        bump = raffle.bump,
+        has_one = authority @ ErrorCode::NotAuthorized,
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    /// CHECK: PDA is only used as a signer for CPIs (tree creator)
+    #[account(
+        seeds = [b"mint_authority", raffle.key().as_ref()],
+        bump
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+
+    /// CHECK: created by Bubblegum CPI
+    #[account(
+        mut,
+        seeds = [merkle_tree.key().as_ref()],
+        bump,
+        seeds::program = bubblegum_program.key()
+    )]
+    pub tree_config: UncheckedAccount<'info>,
+
+    /// CHECK: pre-allocated by the client, initialized by spl-account-compression
+    #[account(mut)]
+    pub merkle_tree: UncheckedAccount<'info>,
+
+    /// CHECK: SPL Noop program
+    #[account(address = SPL_NOOP_ID)]
+    pub log_wrapper: UncheckedAccount<'info>,
+
+    /// CHECK: SPL Account Compression program
+    #[account(address = SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+
+    /// CHECK: Metaplex Bubblegum program
+    #[account(address = mpl_bubblegum::ID)]
+    pub bubblegum_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(allowlist_proof: Option<AllowlistProof>, entry_nonce: Option<u64>)]
+pub struct BuyCompressedTickets<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"raffle", raffle.creator.as_ref(), &raffle.raffle_id.to_le_bytes()],
+        bump = raffle.bump,
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    /// CHECK: PDA is only used as a signer for CPIs (tree delegate + collection authority)
+    #[account(
+        seeds = [b"mint_authority", raffle.key().as_ref()],
+        bump
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"collection_mint", raffle.key().as_ref()],
+        bump
+    )]
+    pub collection_mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: validated by Metaplex during collection verification
+    #[account(
+        mut,
+        seeds = [
+            b"metadata",
+            token_metadata_program.key().as_ref(),
+            collection_mint.key().as_ref()
+        ],
+        bump,
+        seeds::program = token_metadata_program.key()
+    )]
+    pub collection_metadata: UncheckedAccount<'info>,
+
+    /// CHECK: validated by Metaplex during collection verification
+    #[account(
+        seeds = [
+            b"metadata",
+            token_metadata_program.key().as_ref(),
+            collection_mint.key().as_ref(),
+            b"edition"
+        ],
+        bump,
+        seeds::program = token_metadata_program.key()
+    )]
+    pub collection_master_edition: UncheckedAccount<'info>,
+
+    /// CHECK: validated by Bubblegum
+    #[account(
+        mut,
+        seeds = [merkle_tree.key().as_ref()],
+        bump,
+        seeds::program = bubblegum_program.key()
+    )]
+    pub tree_config: UncheckedAccount<'info>,
+
+    /// CHECK: must be the raffle's ticket tree
+    #[account(mut, address = raffle.merkle_tree @ ErrorCode::InvalidMerkleTree)]
+    pub merkle_tree: UncheckedAccount<'info>,
+
+    /// CHECK: Bubblegum PDA signing collection CPIs
+    #[account(
+        seeds = [b"collection_cpi"],
+        bump,
+        seeds::program = bubblegum_program.key()
+    )]
+    pub bubblegum_signer: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + Participant::INIT_SPACE,
+        seeds = [b"participant", raffle.key().as_ref(), payer.key().as_ref()],
+        bump
+    )]
+    pub participant: Account<'info, Participant>,
+
+    pub badge: BadgeAccounts<'info>,
+
+    pub gates: GateAccounts<'info>,
+
+    pub payment: PaymentAccounts<'info>,
+
+    pub entry: EntryAccounts<'info>,
+
+    /// Marks `entry_nonce` as used; only passed for signer-gated raffles
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + EntryNonce::INIT_SPACE,
+        seeds = [
+            b"entry_nonce",
+            raffle.key().as_ref(),
+            payer.key().as_ref(),
+            &entry_nonce.unwrap_or_default().to_le_bytes(),
+        ],
+        bump
+    )]
+    pub entry_record: Option<Account<'info, EntryNonce>>,
+
+    pub referral: ReferralAccounts<'info>,
+
+    /// CHECK: SPL Noop program
+    #[account(address = SPL_NOOP_ID)]
+    pub log_wrapper: UncheckedAccount<'info>,
+
+    /// CHECK: SPL Account Compression program
+    #[account(address = SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+
+    /// CHECK: Metaplex Bubblegum program
+    #[account(address = mpl_bubblegum::ID)]
+    pub bubblegum_program: UncheckedAccount<'info>,
+
+    pub token_metadata_program: Program<'info, Metadata>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitFungibleTickets<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"raffle", raffle.creator.as_ref(), &raffle.raffle_id.to_le_bytes()],
+        bump = raffle.bump,
+        has_one = authority @ ErrorCode::NotAuthorized,
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"fungible_ticket_mint", raffle.key().as_ref()],
+        bump,
+        mint::decimals = 0,
+        mint::authority = raffle,
+        mint::freeze_authority = raffle,
+        mint::token_program = token_program,
+    )]
+    pub ticket_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(amount: u64, allowlist_proof: Option<AllowlistProof>, entry_nonce: Option<u64>)]
+pub struct BuyFungibleTickets<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"raffle", raffle.creator.as_ref(), &raffle.raffle_id.to_le_bytes()],
+        bump = raffle.bump,
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        mut,
+        address = raffle.ticket_mint,
+        mint::token_program = token_program,
+    )]
+    pub ticket_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = ticket_mint,
+        associated_token::authority = payer,
+        associated_token::token_program = token_program,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + Participant::INIT_SPACE,
+        seeds = [b"participant", raffle.key().as_ref(), payer.key().as_ref()],
+        bump
+    )]
+    pub participant: Account<'info, Participant>,
+
+    pub badge: BadgeAccounts<'info>,
+
+    pub gates: GateAccounts<'info>,
+
+    pub payment: PaymentAccounts<'info>,
+
+    pub entry: EntryAccounts<'info>,
+
+    /// Marks `entry_nonce` as used; only passed for signer-gated raffles
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + EntryNonce::INIT_SPACE,
+        seeds = [
+            b"entry_nonce",
+            raffle.key().as_ref(),
+            payer.key().as_ref(),
+            &entry_nonce.unwrap_or_default().to_le_bytes(),
+        ],
+        bump
+    )]
+    pub entry_record: Option<Account<'info, EntryNonce>>,
+
+    pub referral: ReferralAccounts<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SnapshotTickets<'info> {
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"raffle", raffle.creator.as_ref(), &raffle.raffle_id.to_le_bytes()],
+        bump = raffle.bump,
+    )]
+    pub raffle: Account<'info, Raffle>,
 
-        let edition_ctx = CpiContext::new_with_signer(
-            ctx.accounts.token_metadata_program.to_account_info(),
-            edition_accounts,
-            signer_seeds,
-        );
+    #[account(
+        mut,
+        address = raffle.ticket_mint,
+        mint::token_program = token_program,
+    )]
+    pub ticket_mint: InterfaceAccount<'info, Mint>,
 
-        create_master_edition_v3(edition_ctx, Some(0))?;
+    #[account(
+        mut,
+        token::mint = ticket_mint,
+        token::authority = holder,
+        token::token_program = token_program,
+    )]
+    pub holder_token_account: InterfaceAccount<'info, TokenAccount>,
 
-        raffle.claimed = true;
+    #[account(
+        init,
+        payer = holder,
+        space = 8 + TicketSnapshot::INIT_SPACE,
+        seeds = [
+            b"ticket_snapshot",
+            raffle.key().as_ref(),
+            holder.key().as_ref(),
+            &raffle.snapshotted_tickets.to_le_bytes()
+        ],
+        bump
+    )]
+    pub ticket_snapshot: Account<'info, TicketSnapshot>,
 
-        Ok(())
-    }
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
 }
-#[event]
-pub struct WinnerChosen {
-    pub raffle_id: u64,
-    pub winner: Pubkey,
-    pub winner_index: u64,
+
+#[derive(Accounts)]
+pub struct InitToken2022Tickets<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"raffle", raffle.creator.as_ref(), &raffle.raffle_id.to_le_bytes()],
+        bump = raffle.bump,
+        has_one = authority @ ErrorCode::NotAuthorized,
+    )]
+    pub raffle: Account<'info, Raffle>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
-pub struct RevealWinner<'info> {
+#[instruction(allowlist_proof: Option<AllowlistProof>, entry_nonce: Option<u64>)]
+pub struct BuyToken2022Tickets<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
 
     #[account(
         mut,
-    seeds = [b"raffle", payer.key().as_ref(), &raffle.raffle_id.to_le_bytes()],
-bump = raffle.bump,
+        seeds = [b"raffle", raffle.creator.as_ref(), &raffle.raffle_id.to_le_bytes()],
+        bump = raffle.bump,
     )]
     pub raffle: Account<'info, Raffle>,
 
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    /// CHECK: PDA is only used as the ticket metadata update authority
+    #[account(
+        seeds = [b"mint_authority", raffle.key().as_ref()],
+        bump
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+
+    /// CHECK: created and initialized in the instruction with the raffle's extensions
+    #[account(
+        mut,
+        seeds = [b"ticket_mint", raffle.key().as_ref(), raffle.total_num_tickets_bought.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub ticket_mint: UncheckedAccount<'info>,
+
+    /// CHECK: created by the associated token program, which validates the address
+    #[account(mut)]
+    pub user_token_account: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + Participant::INIT_SPACE,
+        seeds = [b"participant", raffle.key().as_ref(), payer.key().as_ref()],
+        bump
+    )]
+    pub participant: Account<'info, Participant>,
+
+    pub badge: BadgeAccounts<'info>,
+
+    pub gates: GateAccounts<'info>,
+
+    pub payment: PaymentAccounts<'info>,
+
+    pub entry: EntryAccounts<'info>,
+
+    /// Marks `entry_nonce` as used; only passed for signer-gated raffles
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + EntryNonce::INIT_SPACE,
+        seeds = [
+            b"entry_nonce",
+            raffle.key().as_ref(),
+            payer.key().as_ref(),
+            &entry_nonce.unwrap_or_default().to_le_bytes(),
+        ],
+        bump
+    )]
+    pub entry_record: Option<Account<'info, EntryNonce>>,
+
+    pub referral: ReferralAccounts<'info>,
+
+    pub token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct ClaimPrize<'info> {
+pub struct InitParticipationBadge<'info> {
+    pub authority: Signer<'info>,
+
     #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"raffle", raffle.creator.as_ref(), &raffle.raffle_id.to_le_bytes()],
+        bump = raffle.bump,
+        has_one = authority @ ErrorCode::NotAuthorized,
+        constraint = raffle.badge_mint == Pubkey::default() @ ErrorCode::BadgeAlreadyInitialized,
+    )]
     pub raffle: Account<'info, Raffle>,
 
+    /// CHECK: created and initialized in the instruction with the non-transferable extension
+    #[account(
+        mut,
+        seeds = [b"badge_mint", raffle.key().as_ref()],
+        bump,
+    )]
+    pub badge_mint: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for minting a participation badge; only required when the raffle
+/// has badges enabled
+#[derive(Accounts)]
+pub struct BadgeAccounts<'info> {
+    /// CHECK: checked against `raffle.badge_mint`
     #[account(mut)]
-    pub winner: Signer<'info>,
+    pub badge_mint: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: buyer's badge ATA, created by the associated token program
+    #[account(mut)]
+    pub badge_token_account: Option<UncheckedAccount<'info>>,
+
+    pub badge_token_program: Option<Program<'info, Token2022>>,
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+}
+
+/// Authority-only updates to raffle settings
+#[derive(Accounts)]
+pub struct ConfigureRaffle<'info> {
+    pub authority: Signer<'info>,
 
     #[account(
-        init,
-        payer = winner,
-        seeds = [b"prize_mint", raffle.key().as_ref()],
-        bump,
-        mint::decimals = 0,
-        mint::authority = prize_mint,
-        mint::freeze_authority = prize_mint
+        mut,
+        seeds = [b"raffle", raffle.creator.as_ref(), &raffle.raffle_id.to_le_bytes()],
+        bump = raffle.bump,
+        has_one = authority @ ErrorCode::NotAuthorized,
     )]
-    pub prize_mint: InterfaceAccount<'info, Mint>,
+    pub raffle: Account<'info, Raffle>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ExtendRaffle<'info> {
+    pub authority: Signer<'info>,
 
     #[account(
-        init_if_needed,
-        payer = winner,
-        associated_token::mint = prize_mint,
-        associated_token::authority = winner
+        mut,
+        seeds = [b"raffle", raffle.creator.as_ref(), &raffle.raffle_id.to_le_bytes()],
+        bump = raffle.bump,
+        has_one = authority @ ErrorCode::NotAuthorized,
     )]
-    pub winner_ata: InterfaceAccount<'info, TokenAccount>,
+    pub raffle: Account<'info, Raffle>,
+}
 
-    /// CHECK: Metaplex Metadata Program
-        #[account(
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CancelRaffle<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
         mut,
-        seeds = [
-            b"metadata",
-            token_metadata_program.key().as_ref(),
-            prize_mint.key().as_ref()
-        ],
-        bump,
-        seeds::program = token_metadata_program.key()
+        seeds = [b"raffle", raffle.creator.as_ref(), &raffle.raffle_id.to_le_bytes()],
+        bump = raffle.bump,
+        has_one = authority @ ErrorCode::NotAuthorized,
     )]
-    pub metadata: UncheckedAccount<'info>,
-    /// CHECK: Master Edition Account
-        #[account(
+    pub raffle: Account<'info, Raffle>,
+}
+
+/// Buyer accounts proving eligibility for gated raffles; only required when the
+/// matching gate is configured
+#[derive(Accounts)]
+pub struct GateAccounts<'info> {
+    /// Buyer's token account holding an NFT from `raffle.gate_collection`
+    pub gate_nft_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Metaplex metadata of the gate NFT, deserialized and checked in `check_entry_gates`
+    pub gate_nft_metadata: Option<UncheckedAccount<'info>>,
+
+    /// Buyer's token account for `raffle.gate_mint`
+    pub gate_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+}
+
+/// Buyer accounts for burn payments; only required when `raffle.burn_mint` is set
+#[derive(Accounts)]
+pub struct PaymentAccounts<'info> {
+    #[account(mut)]
+    pub burn_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// Buyer's token account the payment is burned from
+    #[account(mut)]
+    pub burn_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub burn_token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+/// Accounts for signature-authorized entries; only required when
+/// `raffle.entry_signer` is set
+#[derive(Accounts)]
+pub struct EntryAccounts<'info> {
+    /// CHECK: instructions sysvar
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions_sysvar: Option<UncheckedAccount<'info>>,
+}
+
+/// Referral attribution for a purchase; omit when the buyer has no referrer
+#[derive(Accounts)]
+pub struct ReferralAccounts<'info> {
+    #[account(mut)]
+    pub referral: Option<Account<'info, Referral>>,
+
+    /// Checked against `raffle.referral_vault`
+    #[account(mut)]
+    pub referral_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+}
+
+#[derive(Accounts)]
+pub struct InitReferrals<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
         mut,
-        seeds = [
-            b"metadata",
-            token_metadata_program.key().as_ref(),
-            prize_mint.key().as_ref(),
-            b"edition"
-        ],
-        bump,
-        seeds::program = token_metadata_program.key()
+        seeds = [b"raffle", raffle.creator.as_ref(), &raffle.raffle_id.to_le_bytes()],
+        bump = raffle.bump,
+        has_one = authority @ ErrorCode::NotAuthorized,
     )]
-    pub master_edition: UncheckedAccount<'info>,
+    pub raffle: Account<'info, Raffle>,
+
+    #[account(
+        constraint = raffle.burn_mint == Some(burn_mint.key()) @ ErrorCode::InvalidPaymentMint,
+        mint::token_program = token_program,
+    )]
+    pub burn_mint: InterfaceAccount<'info, Mint>,
 
-    /// CHECK: Metaplex Token Metadata Program
-    pub token_metadata_program: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = burn_mint,
+        associated_token::authority = raffle,
+        associated_token::token_program = token_program,
+    )]
+    pub referral_vault: InterfaceAccount<'info, TokenAccount>,
 
     pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
 }
 
 #[derive(Accounts)]
-#[instruction(raffle_id: u64)]
-pub struct InitConfig<'info> {
+pub struct RegisterReferrer<'info> {
     #[account(mut)]
-    pub payer: Signer<'info>,
+    pub referrer: Signer<'info>,
 
     #[account(
-        init,
-        payer = payer,
-        space = 8 + Raffle::INIT_SPACE,
-    seeds = [b"raffle", payer.key().as_ref(), &raffle_id.to_le_bytes()],
+        seeds = [b"raffle", raffle.creator.as_ref(), &raffle.raffle_id.to_le_bytes()],
+        bump = raffle.bump,
+    )]
+    pub raffle: Account<'info, Raffle>,
 
+    #[account(
+        init,
+        payer = referrer,
+        space = 8 + Referral::INIT_SPACE,
+        seeds = [b"referral", raffle.key().as_ref(), referrer.key().as_ref()],
         bump
     )]
-    pub raffle: Account<'info, Raffle>,
+    pub referral: Account<'info, Referral>,
 
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct InitRaffle<'info> {
-    /// Payer for all inits
-    #[account(mut)]
-    pub payer: Signer<'info>,
+pub struct ClaimReferralRewards<'info> {
+    pub referrer: Signer<'info>,
 
     #[account(
-        mut,
-    seeds = [b"raffle", payer.key().as_ref(), &raffle.raffle_id.to_le_bytes()],
-
+        seeds = [b"raffle", raffle.creator.as_ref(), &raffle.raffle_id.to_le_bytes()],
         bump = raffle.bump,
     )]
     pub raffle: Account<'info, Raffle>,
 
-    /// PDA used as mint authority (separate from the mint being created)
     #[account(
-        seeds = [b"mint_authority", raffle.key().as_ref()],
-        bump
+        mut,
+        seeds = [b"referral", raffle.key().as_ref(), referrer.key().as_ref()],
+        bump = referral.bump,
+        has_one = referrer,
     )]
-    /// CHECK: PDA is only used as a signer for CPIs (mint + metadata); no data read/written
-    pub mint_authority: UncheckedAccount<'info>,
+    pub referral: Account<'info, Referral>,
 
-    /// The collection mint we’re creating (PDA mint)
     #[account(
-        init_if_needed,
-        payer = payer,
-        seeds = [b"collection_mint", raffle.key().as_ref()],
-        bump,
-        mint::decimals = 0,
-        mint::authority = mint_authority,        // ✅ not self
-        mint::freeze_authority = mint_authority, // ✅ not self
-        mint::token_program = token_program
+        constraint = raffle.burn_mint == Some(burn_mint.key()) @ ErrorCode::InvalidPaymentMint,
+        mint::token_program = token_program,
     )]
-    pub collection_mint: InterfaceAccount<'info, Mint>,
+    pub burn_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut, address = raffle.referral_vault)]
+    pub referral_vault: InterfaceAccount<'info, TokenAccount>,
 
-    /// ATA to hold the minted supply (owned by the PDA authority)
     #[account(
-        init_if_needed,
-        payer = payer,
-        associated_token::mint = collection_mint,
-        associated_token::authority = mint_authority,
-        associated_token::token_program = token_program
+        mut,
+        token::mint = burn_mint,
+        token::token_program = token_program,
     )]
-    pub collection_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub referrer_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct InitProtocolConfig<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + ProtocolConfig::INIT_SPACE,
+        seeds = [b"protocol_config"],
+        bump
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + RaffleRegistry::INIT_SPACE,
+        seeds = [b"raffle_registry"],
+        bump
+    )]
+    pub raffle_registry: Account<'info, RaffleRegistry>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::TokenRaffle>,
+
+    #[account(constraint = program_data.upgrade_authority_address == Some(admin.key()) @ ErrorCode::NotAuthorized)]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetProtocolPaused<'info> {
+    pub admin: Signer<'info>,
 
-    /// Metaplex Metadata PDA (derived off the Metaplex program)
     #[account(
         mut,
-        seeds = [
-            b"metadata",
-            token_metadata_program.key().as_ref(),
-            collection_mint.key().as_ref()
-        ],
-        bump,
-        seeds::program = token_metadata_program.key()
+        seeds = [b"protocol_config"],
+        bump = protocol_config.bump,
+        has_one = admin @ ErrorCode::NotAuthorized,
     )]
-    /// CHECK: created & verified by Metaplex CPI
-    pub metadata: UncheckedAccount<'info>,
+    pub protocol_config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    pub new_authority: Signer<'info>,
 
-    /// Metaplex Master Edition PDA
     #[account(
         mut,
-        seeds = [
-            b"metadata",
-            token_metadata_program.key().as_ref(),
-            collection_mint.key().as_ref(),
-            b"edition"
-        ],
-        bump,
-        seeds::program = token_metadata_program.key()
+        seeds = [b"raffle", raffle.creator.as_ref(), &raffle.raffle_id.to_le_bytes()],
+        bump = raffle.bump,
+        constraint = raffle.pending_authority == Some(new_authority.key()) @ ErrorCode::NotAuthorized,
     )]
-    /// CHECK: created & verified by Metaplex CPI
-    pub master_edition: UncheckedAccount<'info>,
-
-    // Programs
-    pub token_program: Interface<'info, TokenInterface>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub token_metadata_program: Program<'info, Metadata>,
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
+    pub raffle: Account<'info, Raffle>,
 }
 
 #[derive(Accounts)]
-pub struct BuyTickets<'info> {
+pub struct MigrateRaffle<'info> {
+    pub authority: Signer<'info>,
+
     #[account(mut)]
     pub payer: Signer<'info>,
 
+    /// CHECK: legacy-layout raffle, validated and rewritten in `migrate_raffle`
+    #[account(mut, owner = crate::ID)]
+    pub raffle: UncheckedAccount<'info>,
+
+    /// CHECK: only checked for existence, which tells whether `init_raffle`
+    /// ran for the legacy raffle
     #[account(
-        mut,
-    seeds = [b"raffle", payer.key().as_ref(), &raffle.raffle_id.to_le_bytes()],
+        seeds = [b"collection_mint", raffle.key().as_ref()],
+        bump,
+    )]
+    pub collection_mint: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FreezeTickets<'info> {
+    pub cranker: Signer<'info>,
 
+    #[account(
+        seeds = [b"raffle", raffle.creator.as_ref(), &raffle.raffle_id.to_le_bytes()],
         bump = raffle.bump,
     )]
     pub raffle: Account<'info, Raffle>,
 
     #[account(
-        init_if_needed,
-        payer = payer,
-        seeds = [ b"ticket_mint",
-            raffle.key().as_ref(), raffle.total_num_tickets_bought.to_le_bytes().as_ref()],
-        bump,
-        mint::decimals = 0,
-        mint::authority = raffle,
-        mint::freeze_authority = raffle,
+        address = raffle.ticket_mint,
         mint::token_program = token_program,
     )]
     pub ticket_mint: InterfaceAccount<'info, Mint>,
 
-    #[account(
-        init_if_needed,
-        payer = payer,
-        associated_token::mint = ticket_mint,
-        associated_token::authority = payer,
-        associated_token::token_program = token_program,
-    )]
-    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
-
     #[account(
         mut,
-        seeds = [b"collection_mint".as_ref(), raffle.key().as_ref()],
-        bump
+        token::mint = ticket_mint,
+        token::token_program = token_program,
     )]
-    pub mint: InterfaceAccount<'info, Mint>,
+    pub ticket_account: InterfaceAccount<'info, TokenAccount>,
 
     pub token_program: Interface<'info, TokenInterface>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
-
 pub struct CommitRandomness<'info> {
-    pub payer: Signer<'info>,
+    /// Raffle authority or operator
+    pub authority: Signer<'info>,
 
     #[account(
         mut,
-    seeds = [b"raffle", payer.key().as_ref(), &raffle.raffle_id.to_le_bytes()],
+    seeds = [b"raffle", raffle.creator.as_ref(), &raffle.raffle_id.to_le_bytes()],
       bump = raffle.bump,
     )]
     pub raffle: Account<'info, Raffle>,
 
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
 
-    // ✅ Optional account — only used in real randomness mode
+    // ✅ Optional account — only used in real randomness mode; must be owned by
+    // the Switchboard on-demand program
     pub randomness_data_account: Option<AccountInfo<'info>>,
 
 
-    pub system_program: Program<'info, System>,
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Raffle {
+    pub authority: Pubkey,
+    pub bump: u8,
+    pub raffle_id: u64,
+    pub winner: Pubkey,
+    pub start_time: u64,
+    pub end_time: i64,
+     #[max_len(50)]
+    pub name: String,
+    pub state: RaffleState,
+    pub randomness: [u8; 32],
+    pub price: u64,
+    pub total_num_tickets_bought: u64,
+    #[max_len(100)]
+    pub ticket_numbers: Vec<Pubkey>,
+    pub max_tickets: u64,
+    pub winner_index: Option<u64>,
+    pub prize_amount: u64,
+    pub nft_config: NftConfig,
+    pub royalty: RoyaltyConfig,
+    pub ticket_mode: TicketMode,
+    /// Bubblegum tree holding compressed tickets, default unless `ticket_mode` is `Compressed`
+    pub merkle_tree: Pubkey,
+    /// Single ticket mint, default unless `ticket_mode` is `Fungible`
+    pub ticket_mint: Pubkey,
+    /// Fungible tickets registered for the draw via `snapshot_tickets`
+    pub snapshotted_tickets: u64,
+    /// Allows `freeze_tickets` on fungible ticket accounts between `end_time`
+    /// and the draw
+    pub freeze_after_sale: bool,
+    /// NFT mode: the prize goes to whoever holds the winning ticket at claim
+    /// time instead of the original buyer
+    pub winner_by_holder: bool,
+    /// NFT mode: mint of the winning ticket, set by `reveal_winner`
+    pub winning_ticket_mint: Pubkey,
+    /// Token-2022 mode: tickets carry the non-transferable extension
+    pub ticket_non_transferable: bool,
+    /// Token-2022 mode: transfer-hook program set on every ticket mint
+    pub transfer_hook_program: Option<Pubkey>,
+    /// Non-transferable participation badge mint, default when badges are disabled
+    pub badge_mint: Pubkey,
+    /// Root of the buyer allowlist Merkle tree, all zeroes when the raffle is open
+    pub allowlist_root: [u8; 32],
+    /// Buyers must hold an NFT from this verified Metaplex collection
+    pub gate_collection: Option<Pubkey>,
+    /// Buyers must hold at least `gate_min_amount` of this mint
+    pub gate_mint: Option<Pubkey>,
+    pub gate_min_amount: u64,
+    /// Tickets are paid for by burning `price` units of this mint
+    pub burn_mint: Option<Pubkey>,
+    /// Total `burn_mint` units burned by buyers
+    pub total_burned: u64,
+    /// Entries must carry an Ed25519 signature from this key
+    pub entry_signer: Option<Pubkey>,
+    /// Each wallet may enter once, with a single ticket
+    pub one_entry_per_wallet: bool,
+    /// Share of each burn payment credited to the buyer's referrer
+    pub referral_bps: u16,
+    /// Raffle-owned token account holding unclaimed referral rewards
+    pub referral_vault: Pubkey,
+    pub pricing: PricingSchedule,
+    /// Set by `init_raffle` once the collection NFT exists
+    pub collection_initialized: bool,
+    pub extension_policy: ExtensionPolicy,
+    /// Times `extend_raffle` has pushed `end_time` later
+    pub extensions_used: u8,
+    /// Original authority; part of the raffle PDA seeds, unaffected by
+    /// authority transfers
+    pub creator: Pubkey,
+    /// Set by `propose_authority` until the new authority accepts
+    pub pending_authority: Option<Pubkey>,
+    /// May run `commit_randomness` with Switchboard randomness and
+    /// `reveal_winner` alongside the authority
+    pub operator: Option<Pubkey>,
+    /// Set by `pause_raffle`; blocks ticket sales and the draw
+    pub paused: bool,
+    /// Fungible mode: seconds after `end_time` during which holders can call
+    /// `snapshot_tickets`
+    pub registration_period: i64,
+    /// Switchboard randomness account bound by `commit_randomness`, read by
+    /// `reveal_winner`; `None` for mock randomness
+    pub randomness_account: Option<Pubkey>,
+}
+
+/// Proof that `keccak(buyer || allowance)` is a leaf of the raffle allowlist.
+/// `allowance` caps the wallet's total tickets; use `u64::MAX` for no cap.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct AllowlistProof {
+    pub allowance: u64,
+    pub proof: Vec<[u8; 32]>,
+}
+
+impl AllowlistProof {
+    /// Verifies against `root` using sorted-pair keccak hashing
+    pub fn verify(&self, root: &[u8; 32], buyer: &Pubkey) -> bool {
+        let leaf = keccak::hashv(&[buyer.as_ref(), &self.allowance.to_le_bytes()]).to_bytes();
+
+        let computed = self.proof.iter().fold(leaf, |node, sibling| {
+            if node <= *sibling {
+                keccak::hashv(&[&node, sibling]).to_bytes()
+            } else {
+                keccak::hashv(&[sibling, &node]).to_bytes()
+            }
+        });
+
+        computed == *root
+    }
+}
+
+/// Accounts shared by every ticket purchase path, borrowed from a buy context
+/// by `purchase()`
+struct Purchase<'a, 'info> {
+    raffle: &'a mut Account<'info, Raffle>,
+    protocol_config: &'a Account<'info, ProtocolConfig>,
+    participant: &'a mut Account<'info, Participant>,
+    payer: &'a Signer<'info>,
+    badge: &'a BadgeAccounts<'info>,
+    gates: &'a GateAccounts<'info>,
+    payment: &'a PaymentAccounts<'info>,
+    entry: &'a EntryAccounts<'info>,
+    entry_record: Option<&'a mut Account<'info, EntryNonce>>,
+    referral: &'a mut ReferralAccounts<'info>,
+    system_program: &'a Program<'info, System>,
+}
+
+/// The buy contexts name their shared accounts identically, so one
+/// `purchase()` body serves all of them
+macro_rules! impl_purchase {
+    ($($context:ident),+ $(,)?) => {$(
+        impl<'info> $context<'info> {
+            fn purchase(&mut self) -> Purchase<'_, 'info> {
+                Purchase {
+                    raffle: &mut self.raffle,
+                    protocol_config: &self.protocol_config,
+                    participant: &mut self.participant,
+                    payer: &self.payer,
+                    badge: &self.badge,
+                    gates: &self.gates,
+                    payment: &self.payment,
+                    entry: &self.entry,
+                    entry_record: self.entry_record.as_mut(),
+                    referral: &mut self.referral,
+                    system_program: &self.system_program,
+                }
+            }
+        }
+    )+};
+}
+
+impl_purchase!(
+    BuyTickets,
+    BuyCompressedTickets,
+    BuyFungibleTickets,
+    BuyToken2022Tickets,
+);
+
+impl Purchase<'_, '_> {
+    /// Checks every buy path runs before minting `tickets` tickets
+    fn check(
+        &mut self,
+        tickets: u64,
+        allowlist_proof: Option<AllowlistProof>,
+        entry_nonce: Option<u64>,
+    ) -> Result<()> {
+        self.raffle.require_state(&[RaffleState::Open])?;
+        require!(
+            self.raffle.has_started(Clock::get()?.unix_timestamp),
+            ErrorCode::RaffleNotStarted
+        );
+        check_not_paused(self.protocol_config, self.raffle)?;
+        self.raffle.require_collection()?;
+
+        let total_num_tickets_bought = self
+            .raffle
+            .total_num_tickets_bought
+            .checked_add(tickets)
+            .ok_or(ErrorCode::Overflow)?;
+        require!(
+            total_num_tickets_bought <= self.raffle.max_tickets,
+            ErrorCode::NoTicketsLeft
+        );
+
+        let buyer = self.payer.key();
+        check_allowlist(self.raffle, self.participant, buyer, tickets, allowlist_proof)?;
+        check_entry_gates(self.raffle, buyer, self.gates)?;
+        check_signed_entry(
+            self.raffle,
+            self.payer,
+            self.entry,
+            self.entry_record.as_deref_mut(),
+            entry_nonce,
+            tickets,
+        )
+    }
+
+    /// Records `tickets` minted tickets: participation, payment, the ticket
+    /// counter and anti-snipe. Returns the events for the caller to emit.
+    fn complete(
+        &mut self,
+        participant_bump: u8,
+        tickets: u64,
+    ) -> Result<(TicketsPurchased, Option<EndTimeChanged>)> {
+        record_participation(
+            self.raffle,
+            self.participant,
+            participant_bump,
+            self.payer,
+            self.badge,
+            self.system_program,
+            tickets,
+        )?;
+        let price_paid =
+            collect_payment(self.raffle, self.payer, self.payment, self.referral, tickets)?;
+
+        let first_ticket_index = self.raffle.total_num_tickets_bought;
+        self.raffle.total_num_tickets_bought = first_ticket_index
+            .checked_add(tickets)
+            .ok_or(ErrorCode::Overflow)?;
+
+        let tickets_purchased = TicketsPurchased {
+            raffle_id: self.raffle.raffle_id,
+            buyer: self.payer.key(),
+            first_ticket_index,
+            tickets,
+            price_paid,
+        };
+
+        let end_time_changed = apply_anti_snipe(self.raffle, Clock::get()?.unix_timestamp);
+
+        Ok((tickets_purchased, end_time_changed))
+    }
+}
+
+/// Creates a PDA the way Anchor's `init` does. Anyone can send lamports to
+/// a PDA before it is created, and `create_account` fails on an account that
+/// already holds lamports, so a prefunded PDA is topped up to `lamports`, then
+/// allocated and assigned instead.
+fn create_pda_account<'info>(
+    payer: &AccountInfo<'info>,
+    account: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    lamports: u64,
+    space: u64,
+    owner: &Pubkey,
+    signer_seeds: &[&[u8]],
+) -> Result<()> {
+    let current_lamports = account.lamports();
+    if current_lamports == 0 {
+        return system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                system_program::CreateAccount {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+                &[signer_seeds],
+            ),
+            lamports,
+            space,
+            owner,
+        );
+    }
+
+    let shortfall = lamports.saturating_sub(current_lamports);
+    if shortfall > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                system_program::Transfer {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+    system_program::allocate(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            system_program::Allocate {
+                account_to_allocate: account.clone(),
+            },
+            &[signer_seeds],
+        ),
+        space,
+    )?;
+    system_program::assign(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            system_program::Assign {
+                account_to_assign: account.clone(),
+            },
+            &[signer_seeds],
+        ),
+        owner,
+    )
+}
+
+fn check_not_paused(protocol_config: &ProtocolConfig, raffle: &Raffle) -> Result<()> {
+    require!(!protocol_config.paused, ErrorCode::ProtocolPaused);
+    require!(!raffle.paused, ErrorCode::RafflePaused);
+
+    Ok(())
+}
+
+fn check_entry_gates(raffle: &Raffle, buyer: Pubkey, gates: &GateAccounts) -> Result<()> {
+    if let Some(gate_collection) = raffle.gate_collection {
+        let token_account = gates
+            .gate_nft_token_account
+            .as_ref()
+            .ok_or(ErrorCode::InvalidNFT)?;
+        let metadata_info = gates
+            .gate_nft_metadata
+            .as_ref()
+            .ok_or(ErrorCode::InvalidNFT)?;
+
+        require_keys_eq!(token_account.owner, buyer, ErrorCode::InvalidNFT);
+        require!(token_account.amount >= 1, ErrorCode::InvalidNFT);
+        require_keys_eq!(
+            *metadata_info.owner,
+            mpl_token_metadata::ID,
+            ErrorCode::InvalidNFT
+        );
+
+        let metadata = MetadataAccount::from_bytes(&metadata_info.try_borrow_data()?)
+            .map_err(|_| ErrorCode::InvalidNFT)?;
+        require!(metadata.key == MetadataKey::MetadataV1, ErrorCode::InvalidNFT);
+        require_keys_eq!(metadata.mint, token_account.mint, ErrorCode::InvalidNFT);
+
+        let collection = metadata.collection.ok_or(ErrorCode::InvalidCollection)?;
+        require_keys_eq!(collection.key, gate_collection, ErrorCode::InvalidCollection);
+        require!(collection.verified, ErrorCode::CollectionNotVerified);
+    }
+
+    if let Some(gate_mint) = raffle.gate_mint {
+        let token_account = gates
+            .gate_token_account
+            .as_ref()
+            .ok_or(ErrorCode::InvalidGateTokenAccount)?;
+
+        require_keys_eq!(token_account.owner, buyer, ErrorCode::InvalidGateTokenAccount);
+        require_keys_eq!(token_account.mint, gate_mint, ErrorCode::InvalidGateTokenAccount);
+        require!(
+            token_account.amount >= raffle.gate_min_amount,
+            ErrorCode::InsufficientGateBalance
+        );
+    }
+
+    Ok(())
+}
+
+/// Pushes `end_time` to `anti_snipe_extension` seconds from now when a purchase
+/// lands within the final `anti_snipe_window` seconds of the sale, returning the
+/// change for the caller to emit.
+fn apply_anti_snipe(raffle: &mut Raffle, now: i64) -> Option<EndTimeChanged> {
+    let policy = &raffle.extension_policy;
+    if policy.anti_snipe_window == 0 {
+        return None;
+    }
+
+    let new_end_time = now.saturating_add(policy.anti_snipe_extension);
+    if raffle.end_time.saturating_sub(now) <= policy.anti_snipe_window
+        && new_end_time > raffle.end_time
+    {
+        let event = EndTimeChanged {
+            raffle_id: raffle.raffle_id,
+            old_end_time: raffle.end_time,
+            new_end_time,
+            anti_snipe: true,
+        };
+        raffle.end_time = new_end_time;
+
+        return Some(event);
+    }
+
+    None
+}
+
+/// Charges the buyer for `tickets` tickets at the scheduled price and returns
+/// the amount paid. Only burn payments are supported: the referrer's
+/// `referral_bps` share is moved to the referral vault and the rest is burned
+/// and added to `raffle.total_burned`. Without a `burn_mint` nothing is charged
+/// and 0 is returned.
+fn collect_payment<'info>(
+    raffle: &mut Account<'info, Raffle>,
+    buyer: &Signer<'info>,
+    payment: &PaymentAccounts<'info>,
+    referral: &mut ReferralAccounts<'info>,
+    tickets: u64,
+) -> Result<u64> {
+    let referral_account = match referral.referral.as_mut() {
+        Some(referral_account) if raffle.referral_bps > 0 => {
+            require_keys_eq!(
+                referral_account.raffle,
+                raffle.key(),
+                ErrorCode::InvalidReferral
+            );
+            require_keys_neq!(
+                referral_account.referrer,
+                buyer.key(),
+                ErrorCode::InvalidReferral
+            );
+
+            referral_account.tickets_referred = referral_account
+                .tickets_referred
+                .checked_add(tickets)
+                .ok_or(ErrorCode::Overflow)?;
+
+            Some(referral_account)
+        }
+        _ => None,
+    };
+
+    let Some(burn_mint) = raffle.burn_mint else {
+        return Ok(0);
+    };
+    let amount = raffle.ticket_cost(tickets, Clock::get()?.unix_timestamp)?;
+
+    let mint = payment
+        .burn_mint
+        .as_ref()
+        .ok_or(ErrorCode::MissingPaymentAccounts)?;
+    let token_account = payment
+        .burn_token_account
+        .as_ref()
+        .ok_or(ErrorCode::MissingPaymentAccounts)?;
+    let token_program = payment
+        .burn_token_program
+        .as_ref()
+        .ok_or(ErrorCode::MissingPaymentAccounts)?;
+
+    require_keys_eq!(mint.key(), burn_mint, ErrorCode::InvalidPaymentMint);
+
+    let mut reward = 0;
+    if let Some(referral_account) = referral_account {
+        let referral_vault = referral
+            .referral_vault
+            .as_ref()
+            .ok_or(ErrorCode::InvalidReferral)?;
+        require_keys_eq!(
+            referral_vault.key(),
+            raffle.referral_vault,
+            ErrorCode::InvalidReferral
+        );
+
+        reward = (u128::from(amount) * u128::from(raffle.referral_bps)
+            / u128::from(MAX_BASIS_POINTS)) as u64;
+
+        token_interface::transfer_checked(
+            CpiContext::new(
+                token_program.to_account_info(),
+                token_interface::TransferChecked {
+                    from: token_account.to_account_info(),
+                    mint: mint.to_account_info(),
+                    to: referral_vault.to_account_info(),
+                    authority: buyer.to_account_info(),
+                },
+            ),
+            reward,
+            mint.decimals,
+        )?;
+
+        referral_account.earned = referral_account
+            .earned
+            .checked_add(reward)
+            .ok_or(ErrorCode::Overflow)?;
+    }
+
+    let burned = amount - reward;
+    token_interface::burn(
+        CpiContext::new(
+            token_program.to_account_info(),
+            token_interface::Burn {
+                mint: mint.to_account_info(),
+                from: token_account.to_account_info(),
+                authority: buyer.to_account_info(),
+            },
+        ),
+        burned,
+    )?;
+
+    raffle.total_burned = raffle
+        .total_burned
+        .checked_add(burned)
+        .ok_or(ErrorCode::Overflow)?;
+
+    Ok(amount)
+}
+
+/// Verifies the entry signature of a signer-gated raffle over
+/// `raffle || buyer || nonce || tickets` and records the nonce in
+/// `entry_record`, which Anchor only creates once, so the same signature cannot
+/// be replayed.
+fn check_signed_entry<'info>(
+    raffle: &Account<'info, Raffle>,
+    buyer: &Signer<'info>,
+    entry: &EntryAccounts<'info>,
+    entry_record: Option<&mut Account<'info, EntryNonce>>,
+    entry_nonce: Option<u64>,
+    tickets: u64,
+) -> Result<()> {
+    let Some(entry_signer) = raffle.entry_signer else {
+        return Ok(());
+    };
+
+    let nonce = entry_nonce.ok_or(ErrorCode::MissingEntrySignature)?;
+    let entry_record = entry_record.ok_or(ErrorCode::MissingEntrySignature)?;
+    let instructions = entry
+        .instructions_sysvar
+        .as_ref()
+        .ok_or(ErrorCode::MissingEntrySignature)?;
+
+    let raffle_key = raffle.key();
+    let buyer_key = buyer.key();
+    let mut message = Vec::with_capacity(80);
+    message.extend_from_slice(raffle_key.as_ref());
+    message.extend_from_slice(buyer_key.as_ref());
+    message.extend_from_slice(&nonce.to_le_bytes());
+    message.extend_from_slice(&tickets.to_le_bytes());
+
+    // The Ed25519 program instruction must directly precede this one
+    let current_index = instructions_sysvar::load_current_index_checked(instructions)?;
+    require!(current_index > 0, ErrorCode::InvalidEntrySignature);
+    let ed25519_ix = instructions_sysvar::load_instruction_at_checked(
+        usize::from(current_index - 1),
+        instructions,
+    )?;
+    require_keys_eq!(
+        ed25519_ix.program_id,
+        ed25519_program::ID,
+        ErrorCode::InvalidEntrySignature
+    );
+    require!(
+        verify_ed25519_data(&ed25519_ix.data, &entry_signer, &message),
+        ErrorCode::InvalidEntrySignature
+    );
+
+    entry_record.raffle = raffle_key;
+    entry_record.buyer = buyer_key;
+    entry_record.nonce = nonce;
+
+    Ok(())
+}
+
+/// Checks that Ed25519 program instruction data holds exactly one signature by
+/// `signer` over `message`, with all offsets pointing into the instruction itself.
+fn verify_ed25519_data(data: &[u8], signer: &Pubkey, message: &[u8]) -> bool {
+    const OFFSETS_START: usize = 2;
+    const OFFSETS_LEN: usize = 14;
+
+    if data.len() < OFFSETS_START + OFFSETS_LEN || data[0] != 1 {
+        return false;
+    }
+
+    let read_u16 = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]);
+    let signature_ix_index = read_u16(OFFSETS_START + 2);
+    let public_key_offset = usize::from(read_u16(OFFSETS_START + 4));
+    let public_key_ix_index = read_u16(OFFSETS_START + 6);
+    let message_offset = usize::from(read_u16(OFFSETS_START + 8));
+    let message_size = usize::from(read_u16(OFFSETS_START + 10));
+    let message_ix_index = read_u16(OFFSETS_START + 12);
+
+    if [signature_ix_index, public_key_ix_index, message_ix_index]
+        .iter()
+        .any(|index| *index != u16::MAX)
+    {
+        return false;
+    }
+
+    data.get(public_key_offset..public_key_offset + 32) == Some(signer.as_ref())
+        && data.get(message_offset..message_offset + message_size) == Some(message)
+}
+
+fn check_allowlist(
+    raffle: &Raffle,
+    participant: &Participant,
+    buyer: Pubkey,
+    tickets: u64,
+    allowlist_proof: Option<AllowlistProof>,
+) -> Result<()> {
+    if raffle.allowlist_root == [0u8; 32] {
+        return Ok(());
+    }
+
+    let allowlist_proof = allowlist_proof.ok_or(ErrorCode::NotAllowlisted)?;
+    require!(
+        allowlist_proof.verify(&raffle.allowlist_root, &buyer),
+        ErrorCode::NotAllowlisted
+    );
+
+    let tickets_after = participant
+        .tickets_bought
+        .checked_add(tickets)
+        .ok_or(ErrorCode::Overflow)?;
+    require!(
+        tickets_after <= allowlist_proof.allowance,
+        ErrorCode::TicketLimitPerUserExceeded
+    );
+
+    Ok(())
+}
+
+/// Per-wallet purchase record for a raffle
+#[account]
+#[derive(InitSpace)]
+pub struct Participant {
+    pub raffle: Pubkey,
+    pub owner: Pubkey,
+    pub tickets_bought: u64,
+    pub badge_minted: bool,
+    pub bump: u8,
+}
+
+/// Updates the buyer's participant record and mints their participation badge
+/// on the first purchase when the raffle has badges enabled.
+fn record_participation<'info>(
+    raffle: &Account<'info, Raffle>,
+    participant: &mut Account<'info, Participant>,
+    participant_bump: u8,
+    buyer: &Signer<'info>,
+    badge: &BadgeAccounts<'info>,
+    system_program: &Program<'info, System>,
+    tickets: u64,
+) -> Result<()> {
+    if raffle.one_entry_per_wallet {
+        require!(
+            participant.tickets_bought == 0 && tickets == 1,
+            ErrorCode::AlreadyEntered
+        );
+    }
+
+    participant.raffle = raffle.key();
+    participant.owner = buyer.key();
+    participant.bump = participant_bump;
+    participant.tickets_bought = participant
+        .tickets_bought
+        .checked_add(tickets)
+        .ok_or(ErrorCode::Overflow)?;
+
+    if raffle.badge_mint == Pubkey::default() || participant.badge_minted {
+        return Ok(());
+    }
+
+    let badge_mint = badge
+        .badge_mint
+        .as_ref()
+        .ok_or(ErrorCode::MissingBadgeAccounts)?;
+    let badge_token_account = badge
+        .badge_token_account
+        .as_ref()
+        .ok_or(ErrorCode::MissingBadgeAccounts)?;
+    let badge_token_program = badge
+        .badge_token_program
+        .as_ref()
+        .ok_or(ErrorCode::MissingBadgeAccounts)?;
+    let associated_token_program = badge
+        .associated_token_program
+        .as_ref()
+        .ok_or(ErrorCode::MissingBadgeAccounts)?;
+    require_keys_eq!(
+        badge_mint.key(),
+        raffle.badge_mint,
+        ErrorCode::InvalidBadgeMint
+    );
+
+    associated_token::create_idempotent(CpiContext::new(
+        associated_token_program.to_account_info(),
+        Create {
+            payer: buyer.to_account_info(),
+            associated_token: badge_token_account.to_account_info(),
+            authority: buyer.to_account_info(),
+            mint: badge_mint.to_account_info(),
+            system_program: system_program.to_account_info(),
+            token_program: badge_token_program.to_account_info(),
+        },
+    ))?;
+
+    let raffle_id = raffle.raffle_id.to_le_bytes();
+    let raffle_seeds: &[&[u8]] = &[
+        b"raffle",
+        raffle.creator.as_ref(),
+        &raffle_id,
+        &[raffle.bump],
+    ];
+
+    token_2022::mint_to(
+        CpiContext::new_with_signer(
+            badge_token_program.to_account_info(),
+            token_2022::MintTo {
+                mint: badge_mint.to_account_info(),
+                to: badge_token_account.to_account_info(),
+                authority: raffle.to_account_info(),
+            },
+            &[raffle_seeds],
+        ),
+        1,
+    )?;
+
+    participant.badge_minted = true;
+
+    Ok(())
+}
+
+/// Program-wide settings controlled by the admin
+#[account]
+#[derive(InitSpace)]
+pub struct ProtocolConfig {
+    pub admin: Pubkey,
+    /// Blocks raffle creation, ticket sales and draws across all raffles
+    pub paused: bool,
+    pub bump: u8,
+}
+
+/// Global raffle counter; raffles created by `init_config` get IDs
+/// `0..next_raffle_id`
+#[account]
+#[derive(InitSpace)]
+pub struct RaffleRegistry {
+    pub next_raffle_id: u64,
+    pub bump: u8,
+}
+
+/// Locates the raffle with a given ID: `["raffle_index", raffle_id]`
+#[account]
+#[derive(InitSpace)]
+pub struct RaffleIndex {
+    pub raffle: Pubkey,
+    pub creator: Pubkey,
+    pub bump: u8,
+}
+
+/// Number of raffles created by an authority
+#[account]
+#[derive(InitSpace)]
+pub struct AuthorityRaffles {
+    pub authority: Pubkey,
+    pub raffle_count: u64,
+    pub bump: u8,
+}
+
+/// A referrer's attributed sales and reward balance for one raffle
+#[account]
+#[derive(InitSpace)]
+pub struct Referral {
+    pub raffle: Pubkey,
+    pub referrer: Pubkey,
+    pub tickets_referred: u64,
+    pub earned: u64,
+    pub claimed: u64,
+    pub bump: u8,
+}
+
+/// Signed-entry nonce consumed by a buyer, at
+/// `["entry_nonce", raffle, buyer, nonce]`
+#[account]
+#[derive(InitSpace)]
+pub struct EntryNonce {
+    pub raffle: Pubkey,
+    pub buyer: Pubkey,
+    pub nonce: u64,
 }
 
+/// A fungible ticket holder's registered range of ticket indexes
 #[account]
 #[derive(InitSpace)]
-pub struct Raffle {
+pub struct TicketSnapshot {
+    pub raffle: Pubkey,
+    pub owner: Pubkey,
+    pub range_start: u64,
+    pub amount: u64,
+    pub bump: u8,
+}
+
+impl TicketSnapshot {
+    pub fn contains(&self, ticket_index: u64) -> bool {
+        ticket_index >= self.range_start && ticket_index - self.range_start < self.amount
+    }
+}
+
+/// Raffle lifecycle: `Draft -> Open -> Closed -> RandomnessRequested -> Drawn
+/// -> Settled`, or `Cancelled` from any state before the draw when no tickets
+/// were sold
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum RaffleState {
+    /// Configured but not yet accepting entries
+    Draft,
+    /// Selling tickets until `end_time`
+    Open,
+    /// Sale over; `Open` raffles read as `Closed` once `end_time` passes
+    Closed,
+    RandomnessRequested,
+    Drawn,
+    /// Prize claimed
+    Settled,
+    Cancelled,
+}
+
+/// `Raffle` layout as first deployed, before per-raffle configuration and the
+/// `state` machine, read by `migrate_raffle`
+#[derive(AnchorDeserialize, InitSpace)]
+pub struct LegacyRaffle {
     pub authority: Pubkey,
     pub bump: u8,
     pub raffle_id: u64,
     pub winner: Pubkey,
     pub start_time: u64,
     pub end_time: i64,
-     #[max_len(50)]
+    #[max_len(50)]
     pub name: String,
     pub winner_chosen: bool,
     pub is_active: bool,
@@ -687,7 +3848,547 @@ pub struct Raffle {
     pub winner_index: Option<u64>,
     pub prize_amount: u64,
     pub claimed: bool,
-    pub randomness_committed: bool
+    pub randomness_committed: bool,
+}
+
+impl LegacyRaffle {
+    /// Legacy raffles sold plain NFT tickets with fixed branding; every field
+    /// added since takes the value that reproduces that behaviour.
+    /// `collection_initialized` tells whether the collection mint exists; an
+    /// active raffle without one could never sell tickets, so it migrates to
+    /// `Draft` for `init_raffle` to finish.
+    pub fn into_raffle(self, collection_initialized: bool) -> Raffle {
+        let state = if self.claimed {
+            RaffleState::Settled
+        } else if self.winner_chosen {
+            RaffleState::Drawn
+        } else if self.randomness_committed {
+            RaffleState::RandomnessRequested
+        } else if self.is_active && collection_initialized {
+            RaffleState::Open
+        } else {
+            RaffleState::Draft
+        };
+
+        Raffle {
+            authority: self.authority,
+            bump: self.bump,
+            raffle_id: self.raffle_id,
+            winner: self.winner,
+            start_time: self.start_time,
+            end_time: self.end_time,
+            name: self.name,
+            state,
+            randomness: self.randomness,
+            price: self.price,
+            total_num_tickets_bought: self.total_num_tickets_bought,
+            ticket_numbers: self.ticket_numbers,
+            max_tickets: self.max_tickets,
+            winner_index: self.winner_index,
+            prize_amount: self.prize_amount,
+            nft_config: NftConfig {
+                name_prefix: "Token Lottery Ticket #".to_string(),
+                symbol: "TICKET".to_string(),
+                collection_uri: "Token Lottery".to_string(),
+                ticket_uri: "Token Lottery".to_string(),
+                prize_uri: "Token Lottery".to_string(),
+            },
+            royalty: RoyaltyConfig {
+                seller_fee_basis_points: 0,
+                creators: Vec::new(),
+            },
+            ticket_mode: TicketMode::Nft,
+            merkle_tree: Pubkey::default(),
+            ticket_mint: Pubkey::default(),
+            snapshotted_tickets: 0,
+            freeze_after_sale: false,
+            winner_by_holder: false,
+            winning_ticket_mint: Pubkey::default(),
+            ticket_non_transferable: false,
+            transfer_hook_program: None,
+            badge_mint: Pubkey::default(),
+            allowlist_root: [0; 32],
+            gate_collection: None,
+            gate_mint: None,
+            gate_min_amount: 0,
+            burn_mint: None,
+            total_burned: 0,
+            entry_signer: None,
+            one_entry_per_wallet: false,
+            referral_bps: 0,
+            referral_vault: Pubkey::default(),
+            pricing: PricingSchedule::default(),
+            collection_initialized,
+            extension_policy: ExtensionPolicy::default(),
+            extensions_used: 0,
+            creator: self.authority,
+            pending_authority: None,
+            operator: None,
+            paused: false,
+            registration_period: 0,
+            randomness_account: None,
+        }
+    }
+}
+
+/// Limits on moving `end_time`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct ExtensionPolicy {
+    /// Number of times `extend_raffle` may push `end_time` later
+    pub max_extensions: u8,
+    /// Purchases this many seconds or less before `end_time` trigger an
+    /// anti-snipe extension; 0 disables it
+    pub anti_snipe_window: i64,
+    /// Seconds from the sniping purchase to the new `end_time`
+    pub anti_snipe_extension: i64,
+}
+
+/// Parameters changed by `update_raffle`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RaffleUpdate {
+    pub name: Option<String>,
+    pub start_time: Option<u64>,
+    pub price: Option<u64>,
+    pub max_tickets: Option<u64>,
+}
+
+/// How the per-ticket price grows with `total_num_tickets_bought`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum PriceCurve {
+    Flat,
+    /// `price + increment * ticket_index`
+    Linear { increment: u64 },
+    /// `price + increment * (ticket_index / step_size)`
+    Step { step_size: u64, increment: u64 },
+}
+
+/// Discount applied to a whole purchase of at least `min_tickets` tickets
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct BulkDiscount {
+    pub min_tickets: u64,
+    pub discount_bps: u16,
+}
+
+/// Ticket pricing on top of `Raffle.price`. Before `early_bird_end` every ticket
+/// costs `early_bird_price`; afterwards `curve` applies. The largest matching
+/// bulk discount is taken off the total. Prices are only charged as burn
+/// payments, so a schedule can only be set while they are enabled.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct PricingSchedule {
+    pub early_bird_price: u64,
+    pub early_bird_end: i64,
+    pub curve: PriceCurve,
+    /// Only fungible purchases buy more than one ticket at a time, so in the
+    /// other modes a discount applies only if its `min_tickets` is 1 or less
+    #[max_len(MAX_BULK_DISCOUNTS)]
+    pub bulk_discounts: Vec<BulkDiscount>,
+}
+
+impl Default for PricingSchedule {
+    fn default() -> Self {
+        Self {
+            early_bird_price: 0,
+            early_bird_end: 0,
+            curve: PriceCurve::Flat,
+            bulk_discounts: Vec::new(),
+        }
+    }
+}
+
+impl PricingSchedule {
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.bulk_discounts.len() <= MAX_BULK_DISCOUNTS,
+            ErrorCode::InvalidPricing
+        );
+        require!(
+            self.bulk_discounts
+                .iter()
+                .all(|discount| discount.discount_bps <= MAX_BASIS_POINTS),
+            ErrorCode::InvalidPricing
+        );
+        if let PriceCurve::Step { step_size, .. } = self.curve {
+            require!(step_size > 0, ErrorCode::InvalidPricing);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum TicketMode {
+    /// One mint + master edition per ticket
+    Nft,
+    /// Bubblegum compressed NFTs in a raffle-owned tree
+    Compressed,
+    /// Token amounts of a single `ticket_mint`, registered for the draw by
+    /// `snapshot_tickets` after `end_time`
+    Fungible,
+    /// One Token-2022 mint per ticket with on-chain token metadata
+    Token2022,
+}
+
+/// Leaf fields a compressed ticket holder supplies to prove ownership of the
+/// winning ticket. Proof nodes are passed as remaining accounts.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct TicketLeaf {
+    pub root: [u8; 32],
+    pub delegate: Pubkey,
+    pub data_hash: [u8; 32],
+    pub creator_hash: [u8; 32],
+}
+
+/// Verifies via spl-account-compression that the leaf at `ticket_index` is a
+/// Bubblegum V1 leaf owned by `owner`.
+fn verify_ticket_leaf<'info>(
+    compression_program: &AccountInfo<'info>,
+    merkle_tree: &AccountInfo<'info>,
+    proof: &[AccountInfo<'info>],
+    ticket_leaf: &TicketLeaf,
+    owner: Pubkey,
+    ticket_index: u64,
+) -> Result<()> {
+    let leaf = LeafSchema::V1 {
+        id: mpl_bubblegum::utils::get_asset_id(merkle_tree.key, ticket_index),
+        owner,
+        delegate: ticket_leaf.delegate,
+        nonce: ticket_index,
+        data_hash: ticket_leaf.data_hash,
+        creator_hash: ticket_leaf.creator_hash,
+    };
+    let index = u32::try_from(ticket_index).map_err(|_| ErrorCode::Overflow)?;
+
+    let mut data = VERIFY_LEAF_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&ticket_leaf.root);
+    data.extend_from_slice(&leaf.hash());
+    data.extend_from_slice(&index.to_le_bytes());
+
+    let mut accounts = vec![AccountMeta::new_readonly(*merkle_tree.key, false)];
+    accounts.extend(
+        proof
+            .iter()
+            .map(|node| AccountMeta::new_readonly(*node.key, false)),
+    );
+
+    let mut account_infos = vec![merkle_tree.clone()];
+    account_infos.extend_from_slice(proof);
+
+    invoke(
+        &Instruction {
+            program_id: *compression_program.key,
+            accounts,
+            data,
+        },
+        &account_infos,
+    )?;
+
+    Ok(())
+}
+
+impl Raffle {
+    /// Whether `signer` may commit randomness and reveal the winner
+    pub fn can_draw(&self, signer: Pubkey) -> bool {
+        signer == self.authority || self.operator == Some(signer)
+    }
+
+    /// Lifecycle state at `now`, treating an `Open` raffle past `end_time` as
+    /// `Closed`
+    pub fn state_at(&self, now: i64) -> RaffleState {
+        if self.state == RaffleState::Open && now >= self.end_time {
+            RaffleState::Closed
+        } else {
+            self.state
+        }
+    }
+
+    /// Whether ticket sales have reached `start_time`
+    pub fn has_started(&self, now: i64) -> bool {
+        u64::try_from(now).is_ok_and(|now| now >= self.start_time)
+    }
+
+    pub fn require_state(&self, allowed: &[RaffleState]) -> Result<()> {
+        let state = self.state_at(Clock::get()?.unix_timestamp);
+        require!(allowed.contains(&state), ErrorCode::InvalidRaffleState);
+
+        Ok(())
+    }
+
+    /// Settings that change what buyers get or pay are locked once a ticket has
+    /// been sold
+    pub fn require_no_sales(&self) -> Result<()> {
+        require!(
+            self.total_num_tickets_bought == 0,
+            ErrorCode::TicketsAlreadySold
+        );
+
+        Ok(())
+    }
+
+    /// Moves `end_time` to a future `new_end_time`. Before the first ticket
+    /// sale it can move either way; afterwards it can only be pushed later,
+    /// using up one of `extension_policy.max_extensions`. Returns the change
+    /// for the caller to emit.
+    pub fn change_end_time(&mut self, new_end_time: i64, now: i64) -> Result<EndTimeChanged> {
+        require!(
+            new_end_time > now && new_end_time != self.end_time,
+            ErrorCode::InvalidEndTime
+        );
+
+        if self.total_num_tickets_bought > 0 {
+            require!(
+                new_end_time > self.end_time,
+                ErrorCode::TicketsAlreadySold
+            );
+            require!(
+                self.extensions_used < self.extension_policy.max_extensions,
+                ErrorCode::TooManyExtensions
+            );
+            self.extensions_used += 1;
+        }
+
+        let event = EndTimeChanged {
+            raffle_id: self.raffle_id,
+            old_end_time: self.end_time,
+            new_end_time,
+            anti_snipe: false,
+        };
+        self.end_time = new_end_time;
+
+        Ok(event)
+    }
+
+    /// NFT and compressed tickets are minted into the raffle collection, so
+    /// those modes cannot sell before `init_raffle` has created it
+    pub fn require_collection(&self) -> Result<()> {
+        if matches!(self.ticket_mode, TicketMode::Nft | TicketMode::Compressed) {
+            require!(
+                self.collection_initialized,
+                ErrorCode::CollectionNotInitialized
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Total cost of the next `tickets` tickets at time `now`
+    pub fn ticket_cost(&self, tickets: u64, now: i64) -> Result<u64> {
+        let pricing = &self.pricing;
+        let count = u128::from(tickets);
+        let start = u128::from(self.total_num_tickets_bought);
+        let overflow = || error!(ErrorCode::Overflow);
+
+        let total = if now < pricing.early_bird_end {
+            count
+                .checked_mul(u128::from(pricing.early_bird_price))
+                .ok_or_else(overflow)?
+        } else {
+            // Sum of the curve's per-ticket increases over indexes [start, start + count)
+            let increase = match pricing.curve {
+                PriceCurve::Flat => 0,
+                PriceCurve::Linear { increment } => {
+                    let index_sum = count
+                        .checked_mul(start)
+                        .and_then(|sum| {
+                            sum.checked_add(count.checked_mul(count.saturating_sub(1))? / 2)
+                        })
+                        .ok_or_else(overflow)?;
+                    u128::from(increment)
+                        .checked_mul(index_sum)
+                        .ok_or_else(overflow)?
+                }
+                PriceCurve::Step {
+                    step_size,
+                    increment,
+                } => {
+                    let step_size = u128::from(step_size);
+                    let steps_before = |end: u128| {
+                        let (full, rest) = (end / step_size, end % step_size);
+                        step_size
+                            .checked_mul(full)?
+                            .checked_mul(full.saturating_sub(1))?
+                            .checked_div(2)?
+                            .checked_add(full.checked_mul(rest)?)
+                    };
+                    let end = start.checked_add(count).ok_or_else(overflow)?;
+                    let steps = steps_before(end)
+                        .zip(steps_before(start))
+                        .and_then(|(end, start)| end.checked_sub(start))
+                        .ok_or_else(overflow)?;
+                    u128::from(increment)
+                        .checked_mul(steps)
+                        .ok_or_else(overflow)?
+                }
+            };
+            count
+                .checked_mul(u128::from(self.price))
+                .and_then(|base| base.checked_add(increase))
+                .ok_or_else(overflow)?
+        };
+
+        let discount_bps = pricing
+            .bulk_discounts
+            .iter()
+            .filter(|discount| tickets >= discount.min_tickets)
+            .map(|discount| discount.discount_bps)
+            .max()
+            .unwrap_or(0);
+        let total = MAX_BASIS_POINTS
+            .checked_sub(discount_bps)
+            .and_then(|bps| total.checked_mul(u128::from(bps)))
+            .ok_or_else(overflow)?
+            / u128::from(MAX_BASIS_POINTS);
+
+        u64::try_from(total).map_err(|_| overflow())
+    }
+
+    /// Whether every ticket is free under the current pricing
+    pub fn is_free(&self) -> bool {
+        let early_bird_free =
+            self.pricing.early_bird_end == 0 || self.pricing.early_bird_price == 0;
+
+        self.price == 0 && early_bird_free && self.pricing.curve == PriceCurve::Flat
+    }
+
+    /// End of the fungible registration period
+    pub fn registration_deadline(&self) -> i64 {
+        self.end_time.saturating_add(self.registration_period)
+    }
+
+    /// Whether the drawable ticket count is final: always outside fungible
+    /// mode, otherwise once every ticket is registered or the deadline passes
+    pub fn registration_closed(&self, now: i64) -> bool {
+        self.ticket_mode != TicketMode::Fungible
+            || self.snapshotted_tickets == self.total_num_tickets_bought
+            || now >= self.registration_deadline()
+    }
+
+    /// Number of tickets the winning index is drawn from
+    pub fn drawable_tickets(&self) -> u64 {
+        match self.ticket_mode {
+            TicketMode::Nft | TicketMode::Compressed | TicketMode::Token2022 => {
+                self.total_num_tickets_bought
+            }
+            TicketMode::Fungible => self.snapshotted_tickets,
+        }
+    }
+
+    /// Creators for every NFT the raffle mints. The mint authority PDA is always
+    /// listed first with a zero share so the program can sign as a verified
+    /// creator; royalties go to the configured creators, or to the raffle
+    /// authority when none are configured.
+    pub fn metaplex_creators(&self, mint_authority: Pubkey, verified: bool) -> Vec<Creator> {
+        let mut creators = vec![Creator {
+            address: mint_authority,
+            verified,
+            share: 0,
+        }];
+
+        if self.royalty.creators.is_empty() {
+            creators.push(Creator {
+                address: self.authority,
+                verified: false,
+                share: 100,
+            });
+        } else {
+            creators.extend(self.royalty.creators.iter().map(|creator| Creator {
+                address: creator.address,
+                verified: false,
+                share: creator.share,
+            }));
+        }
+
+        creators
+    }
+}
+
+/// Per-raffle branding for the collection, ticket and prize NFTs
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct NftConfig {
+    /// Ticket names are rendered as `{name_prefix}{ticket_index}`
+    #[max_len(MAX_NAME_LENGTH)]
+    pub name_prefix: String,
+    #[max_len(MAX_SYMBOL_LENGTH)]
+    pub symbol: String,
+    #[max_len(MAX_URI_LENGTH)]
+    pub collection_uri: String,
+    /// Off-chain JSON for each ticket; `{id}` is replaced with the ticket index
+    #[max_len(MAX_URI_LENGTH)]
+    pub ticket_uri: String,
+    #[max_len(MAX_URI_LENGTH)]
+    pub prize_uri: String,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct RoyaltyCreator {
+    pub address: Pubkey,
+    pub share: u8,
+}
+
+/// Secondary-sale royalties applied to the collection, ticket and prize NFTs
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct RoyaltyConfig {
+    pub seller_fee_basis_points: u16,
+    #[max_len(MAX_ROYALTY_CREATORS)]
+    pub creators: Vec<RoyaltyCreator>,
+}
+
+impl RoyaltyConfig {
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.seller_fee_basis_points <= MAX_BASIS_POINTS,
+            ErrorCode::InvalidRoyaltyBasisPoints
+        );
+        require!(
+            self.creators.len() <= MAX_ROYALTY_CREATORS,
+            ErrorCode::TooManyCreators
+        );
+
+        if !self.creators.is_empty() {
+            let total_share = self
+                .creators
+                .iter()
+                .try_fold(0u8, |total, creator| total.checked_add(creator.share))
+                .ok_or(ErrorCode::InvalidCreatorShares)?;
+            require!(total_share == 100, ErrorCode::InvalidCreatorShares);
+        }
+
+        Ok(())
+    }
+}
+
+impl NftConfig {
+    /// Rejects any string that would not fit Metaplex limits, including the
+    /// rendered ticket name and URI for the highest ticket index.
+    pub fn validate(&self, max_tickets: u64) -> Result<()> {
+        let last_index = max_tickets.saturating_sub(1);
+
+        require!(
+            self.ticket_name(last_index).len() <= MAX_NAME_LENGTH,
+            ErrorCode::NameTooLong
+        );
+        require!(
+            self.symbol.len() <= MAX_SYMBOL_LENGTH,
+            ErrorCode::SymbolTooLong
+        );
+        require!(
+            self.collection_uri.len() <= MAX_URI_LENGTH
+                && self.ticket_uri.len() <= MAX_URI_LENGTH
+                && self.ticket_uri(last_index).len() <= MAX_URI_LENGTH
+                && self.prize_uri.len() <= MAX_URI_LENGTH,
+            ErrorCode::UriTooLong
+        );
+
+        Ok(())
+    }
+
+    pub fn ticket_name(&self, ticket_index: u64) -> String {
+        format!("{}{}", self.name_prefix, ticket_index)
+    }
+
+    pub fn ticket_uri(&self, ticket_index: u64) -> String {
+        self.ticket_uri
+            .replace(TICKET_ID_PLACEHOLDER, &ticket_index.to_string())
+    }
 }
 
 #[error_code]
@@ -753,4 +4454,619 @@ pub enum ErrorCode {
     CollectionNotVerified,
     #[msg("Missing randomness account")]
     MissingRandomnessAccount,
+    #[msg("Name too long")]
+    NameTooLong,
+    #[msg("Symbol too long")]
+    SymbolTooLong,
+    #[msg("URI too long")]
+    UriTooLong,
+    #[msg("Royalty basis points exceed 10000")]
+    InvalidRoyaltyBasisPoints,
+    #[msg("Too many creators")]
+    TooManyCreators,
+    #[msg("Creator shares must sum to 100")]
+    InvalidCreatorShares,
+    #[msg("Instruction not supported in this ticket mode")]
+    InvalidTicketMode,
+    #[msg("Tickets already sold")]
+    TicketsAlreadySold,
+    #[msg("Merkle tree too small for max tickets")]
+    TreeTooSmall,
+    #[msg("Invalid merkle tree")]
+    InvalidMerkleTree,
+    #[msg("Missing compressed ticket leaf proof")]
+    MissingLeafProof,
+    #[msg("Invalid ticket amount")]
+    InvalidTicketAmount,
+    #[msg("Missing ticket snapshot")]
+    MissingTicketSnapshot,
+    #[msg("Ticket freezing not enabled for this raffle")]
+    FreezeNotEnabled,
+    #[msg("Missing winning ticket accounts")]
+    MissingWinningTicket,
+    #[msg("Participation badge already initialized")]
+    BadgeAlreadyInitialized,
+    #[msg("Missing participation badge accounts")]
+    MissingBadgeAccounts,
+    #[msg("Invalid participation badge mint")]
+    InvalidBadgeMint,
+    #[msg("Buyer is not on the allowlist")]
+    NotAllowlisted,
+    #[msg("Invalid gate token account")]
+    InvalidGateTokenAccount,
+    #[msg("Gate token balance too low")]
+    InsufficientGateBalance,
+    #[msg("Missing payment accounts")]
+    MissingPaymentAccounts,
+    #[msg("Invalid payment mint")]
+    InvalidPaymentMint,
+    #[msg("Entry signer requires a free raffle")]
+    EntrySignerRequiresFreeRaffle,
+    #[msg("Missing entry signature")]
+    MissingEntrySignature,
+    #[msg("Invalid entry signature")]
+    InvalidEntrySignature,
+    #[msg("Wallet has already entered this raffle")]
+    AlreadyEntered,
+    #[msg("Referral basis points exceed 10000")]
+    InvalidReferralBasisPoints,
+    #[msg("Invalid referral")]
+    InvalidReferral,
+    #[msg("No referral rewards to claim")]
+    NoReferralRewards,
+    #[msg("Invalid pricing schedule")]
+    InvalidPricing,
+    #[msg("Invalid raffle state for this instruction")]
+    InvalidRaffleState,
+    #[msg("Raffle already migrated")]
+    AlreadyMigrated,
+    #[msg("Collection not initialized")]
+    CollectionNotInitialized,
+    #[msg("Invalid end time")]
+    InvalidEndTime,
+    #[msg("Maximum number of extensions reached")]
+    TooManyExtensions,
+    #[msg("Protocol is paused")]
+    ProtocolPaused,
+    #[msg("Raffle is paused")]
+    RafflePaused,
+    #[msg("No tickets to draw from")]
+    NoDrawableTickets,
+    #[msg("Registration period must be positive")]
+    InvalidRegistrationPeriod,
+    #[msg("Ticket registration has closed")]
+    RegistrationClosed,
+    #[msg("Ticket registration is still open")]
+    RegistrationOpen,
+    #[msg("Burn mint cannot change once referrals are enabled")]
+    BurnMintLockedByReferrals,
+    #[msg("Pricing schedules require burn payments")]
+    PricingRequiresBurnPayment,
+    #[msg("Raffle has not started yet")]
+    RaffleNotStarted,
+    #[msg("Randomness must be committed in the slot after it was requested")]
+    RandomnessExpired,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nft_config() -> NftConfig {
+        NftConfig {
+            name_prefix: "Ticket #".to_string(),
+            symbol: "TKT".to_string(),
+            collection_uri: "https://example.com/collection.json".to_string(),
+            ticket_uri: "https://example.com/{id}.json".to_string(),
+            prize_uri: "https://example.com/prize.json".to_string(),
+        }
+    }
+
+    #[test]
+    fn nft_config_renders_ticket_index() {
+        let config = nft_config();
+
+        assert_eq!(config.ticket_name(7), "Ticket #7");
+        assert_eq!(config.ticket_uri(7), "https://example.com/7.json");
+    }
+
+    #[test]
+    fn nft_config_checks_rendered_name_of_last_ticket() {
+        let config = NftConfig {
+            name_prefix: "x".repeat(MAX_NAME_LENGTH - 2),
+            ..nft_config()
+        };
+
+        assert!(config.validate(100).is_ok());
+        assert_eq!(
+            config.validate(101).unwrap_err(),
+            ErrorCode::NameTooLong.into()
+        );
+    }
+
+    #[test]
+    fn nft_config_rejects_long_symbol_and_uris() {
+        let long_symbol = NftConfig {
+            symbol: "x".repeat(MAX_SYMBOL_LENGTH + 1),
+            ..nft_config()
+        };
+        assert_eq!(
+            long_symbol.validate(1).unwrap_err(),
+            ErrorCode::SymbolTooLong.into()
+        );
+
+        let long_ticket_uri = NftConfig {
+            ticket_uri: format!("{}{TICKET_ID_PLACEHOLDER}", "x".repeat(MAX_URI_LENGTH - 4)),
+            ..nft_config()
+        };
+        assert!(long_ticket_uri.validate(10_000).is_ok());
+        assert_eq!(
+            long_ticket_uri.validate(10_001).unwrap_err(),
+            ErrorCode::UriTooLong.into()
+        );
+    }
+
+    fn creator(share: u8) -> RoyaltyCreator {
+        RoyaltyCreator {
+            address: Pubkey::new_unique(),
+            share,
+        }
+    }
+
+    #[test]
+    fn royalty_config_accepts_shares_summing_to_100() {
+        let royalty = RoyaltyConfig {
+            seller_fee_basis_points: 500,
+            creators: vec![creator(60), creator(40)],
+        };
+        assert!(royalty.validate().is_ok());
+
+        let no_creators = RoyaltyConfig {
+            seller_fee_basis_points: MAX_BASIS_POINTS,
+            creators: Vec::new(),
+        };
+        assert!(no_creators.validate().is_ok());
+    }
+
+    #[test]
+    fn royalty_config_rejects_invalid_settings() {
+        let cases = [
+            (MAX_BASIS_POINTS + 1, vec![creator(100)], ErrorCode::InvalidRoyaltyBasisPoints),
+            (0, vec![creator(20); MAX_ROYALTY_CREATORS + 1], ErrorCode::TooManyCreators),
+            (0, vec![creator(60), creator(30)], ErrorCode::InvalidCreatorShares),
+            (0, vec![creator(200), creator(100)], ErrorCode::InvalidCreatorShares),
+        ];
+
+        for (seller_fee_basis_points, creators, error) in cases {
+            let royalty = RoyaltyConfig {
+                seller_fee_basis_points,
+                creators,
+            };
+            assert_eq!(royalty.validate().unwrap_err(), error.into());
+        }
+    }
+
+    fn allowlist_leaf(buyer: &Pubkey, allowance: u64) -> [u8; 32] {
+        keccak::hashv(&[buyer.as_ref(), &allowance.to_le_bytes()]).to_bytes()
+    }
+
+    fn hash_pair(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
+        let (left, right) = if a <= b { (a, b) } else { (b, a) };
+        keccak::hashv(&[&left, &right]).to_bytes()
+    }
+
+    #[test]
+    fn allowlist_proof_verifies_against_sorted_pair_tree() {
+        let buyers = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        let leaves = [
+            allowlist_leaf(&buyers[0], 1),
+            allowlist_leaf(&buyers[1], 5),
+            allowlist_leaf(&buyers[2], u64::MAX),
+        ];
+        let node = hash_pair(leaves[0], leaves[1]);
+        let root = hash_pair(node, leaves[2]);
+
+        let first = AllowlistProof {
+            allowance: 1,
+            proof: vec![leaves[1], leaves[2]],
+        };
+        assert!(first.verify(&root, &buyers[0]));
+        assert!(!first.verify(&root, &buyers[1]));
+
+        let last = AllowlistProof {
+            allowance: u64::MAX,
+            proof: vec![node],
+        };
+        assert!(last.verify(&root, &buyers[2]));
+
+        let inflated = AllowlistProof {
+            allowance: 2,
+            proof: vec![leaves[1], leaves[2]],
+        };
+        assert!(!inflated.verify(&root, &buyers[0]));
+    }
+
+    #[test]
+    fn allowlist_proof_of_single_leaf_is_empty() {
+        let buyer = Pubkey::new_unique();
+        let proof = AllowlistProof {
+            allowance: 3,
+            proof: Vec::new(),
+        };
+
+        assert!(proof.verify(&allowlist_leaf(&buyer, 3), &buyer));
+    }
+
+    /// Ed25519 program instruction data for one signature, laid out as the
+    /// Solana SDK does: offsets, then public key, signature and message
+    fn ed25519_data(signer: &Pubkey, message: &[u8], instruction_index: u16) -> Vec<u8> {
+        const PUBLIC_KEY_OFFSET: u16 = 16;
+        const SIGNATURE_OFFSET: u16 = PUBLIC_KEY_OFFSET + 32;
+        const MESSAGE_OFFSET: u16 = SIGNATURE_OFFSET + 64;
+
+        let mut data = vec![1, 0];
+        for value in [
+            SIGNATURE_OFFSET,
+            instruction_index,
+            PUBLIC_KEY_OFFSET,
+            instruction_index,
+            MESSAGE_OFFSET,
+            message.len() as u16,
+            instruction_index,
+        ] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(signer.as_ref());
+        data.extend_from_slice(&[0; 64]);
+        data.extend_from_slice(message);
+        data
+    }
+
+    #[test]
+    fn ed25519_data_matches_signer_and_message() {
+        let signer = Pubkey::new_unique();
+        let message = [7u8; 80];
+        let data = ed25519_data(&signer, &message, u16::MAX);
+
+        assert!(verify_ed25519_data(&data, &signer, &message));
+        assert!(!verify_ed25519_data(&data, &Pubkey::new_unique(), &message));
+        assert!(!verify_ed25519_data(&data, &signer, &[8u8; 80]));
+        assert!(!verify_ed25519_data(&data, &signer, &message[..72]));
+    }
+
+    #[test]
+    fn ed25519_data_must_reference_its_own_instruction() {
+        let signer = Pubkey::new_unique();
+        let message = [7u8; 80];
+
+        assert!(!verify_ed25519_data(
+            &ed25519_data(&signer, &message, 0),
+            &signer,
+            &message
+        ));
+    }
+
+    #[test]
+    fn ed25519_data_rejects_malformed_input() {
+        let signer = Pubkey::new_unique();
+        let message = [7u8; 80];
+        let data = ed25519_data(&signer, &message, u16::MAX);
+
+        let mut two_signatures = data.clone();
+        two_signatures[0] = 2;
+        assert!(!verify_ed25519_data(&two_signatures, &signer, &message));
+
+        assert!(!verify_ed25519_data(&data[..15], &signer, &message));
+        assert!(!verify_ed25519_data(&data[..data.len() - 1], &signer, &message));
+    }
+
+    fn legacy_raffle() -> LegacyRaffle {
+        LegacyRaffle {
+            authority: Pubkey::new_unique(),
+            bump: 254,
+            raffle_id: 3,
+            winner: Pubkey::default(),
+            start_time: 0,
+            end_time: 1_000,
+            name: "Raffle".to_string(),
+            winner_chosen: false,
+            is_active: true,
+            randomness: [0; 32],
+            price: 100,
+            total_num_tickets_bought: 0,
+            ticket_numbers: Vec::new(),
+            max_tickets: 100,
+            winner_index: None,
+            prize_amount: 0,
+            claimed: false,
+            randomness_committed: false,
+        }
+    }
+
+    fn priced_raffle(price: u64, sold: u64, pricing: PricingSchedule) -> Raffle {
+        let mut raffle = legacy_raffle().into_raffle(true);
+        raffle.price = price;
+        raffle.total_num_tickets_bought = sold;
+        raffle.pricing = pricing;
+        raffle
+    }
+
+    fn curve(curve: PriceCurve) -> PricingSchedule {
+        PricingSchedule {
+            curve,
+            ..PricingSchedule::default()
+        }
+    }
+
+    /// Per-ticket sum the closed forms in `ticket_cost` must match
+    fn summed_cost(price: u64, sold: u64, tickets: u64, curve: PriceCurve) -> u64 {
+        (sold..sold + tickets)
+            .map(|index| match curve {
+                PriceCurve::Flat => price,
+                PriceCurve::Linear { increment } => price + increment * index,
+                PriceCurve::Step {
+                    step_size,
+                    increment,
+                } => price + increment * (index / step_size),
+            })
+            .sum()
+    }
+
+    #[test]
+    fn ticket_cost_flat() {
+        let raffle = priced_raffle(100, 0, PricingSchedule::default());
+
+        assert_eq!(raffle.ticket_cost(3, 0).unwrap(), 300);
+    }
+
+    #[test]
+    fn ticket_cost_curves_match_per_ticket_sum() {
+        let curves = [
+            PriceCurve::Linear { increment: 7 },
+            PriceCurve::Step {
+                step_size: 1,
+                increment: 3,
+            },
+            PriceCurve::Step {
+                step_size: 4,
+                increment: 25,
+            },
+        ];
+
+        for curve_kind in curves {
+            for sold in [0, 1, 3, 4, 9] {
+                for tickets in [1, 2, 5, 8, 13] {
+                    let raffle = priced_raffle(100, sold, curve(curve_kind));
+                    assert_eq!(
+                        raffle.ticket_cost(tickets, 0).unwrap(),
+                        summed_cost(100, sold, tickets, curve_kind),
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn ticket_cost_early_bird_ignores_curve_until_it_ends() {
+        let pricing = PricingSchedule {
+            early_bird_price: 40,
+            early_bird_end: 500,
+            ..curve(PriceCurve::Linear { increment: 10 })
+        };
+        let raffle = priced_raffle(100, 2, pricing);
+
+        assert_eq!(raffle.ticket_cost(3, 499).unwrap(), 120);
+        assert_eq!(raffle.ticket_cost(3, 500).unwrap(), 390);
+    }
+
+    #[test]
+    fn ticket_cost_takes_largest_matching_bulk_discount() {
+        let pricing = PricingSchedule {
+            bulk_discounts: vec![
+                BulkDiscount {
+                    min_tickets: 5,
+                    discount_bps: 2_500,
+                },
+                BulkDiscount {
+                    min_tickets: 2,
+                    discount_bps: 1_000,
+                },
+            ],
+            ..PricingSchedule::default()
+        };
+        let raffle = priced_raffle(100, 0, pricing);
+
+        assert_eq!(raffle.ticket_cost(1, 0).unwrap(), 100);
+        assert_eq!(raffle.ticket_cost(4, 0).unwrap(), 360);
+        assert_eq!(raffle.ticket_cost(5, 0).unwrap(), 375);
+    }
+
+    #[test]
+    fn ticket_cost_reports_overflow() {
+        let flat = priced_raffle(u64::MAX, 0, PricingSchedule::default());
+        assert_eq!(
+            flat.ticket_cost(2, 0).unwrap_err(),
+            ErrorCode::Overflow.into()
+        );
+
+        let linear = priced_raffle(
+            0,
+            u64::MAX - 1,
+            curve(PriceCurve::Linear {
+                increment: u64::MAX,
+            }),
+        );
+        assert_eq!(
+            linear.ticket_cost(1, 0).unwrap_err(),
+            ErrorCode::Overflow.into()
+        );
+
+        let step = priced_raffle(
+            0,
+            u64::MAX,
+            curve(PriceCurve::Step {
+                step_size: 1,
+                increment: 1,
+            }),
+        );
+        assert_eq!(
+            step.ticket_cost(u64::MAX, 0).unwrap_err(),
+            ErrorCode::Overflow.into()
+        );
+    }
+
+    #[test]
+    fn sales_start_at_start_time() {
+        let mut raffle = legacy_raffle().into_raffle(true);
+        raffle.start_time = 500;
+
+        assert!(!raffle.has_started(-1));
+        assert!(!raffle.has_started(499));
+        assert!(raffle.has_started(500));
+        assert!(raffle.has_started(501));
+    }
+
+    fn snipeable_raffle(window: i64, extension: i64) -> Raffle {
+        let mut raffle = legacy_raffle().into_raffle(true);
+        raffle.extension_policy = ExtensionPolicy {
+            max_extensions: 0,
+            anti_snipe_window: window,
+            anti_snipe_extension: extension,
+        };
+        raffle
+    }
+
+    #[test]
+    fn anti_snipe_extends_inside_the_window() {
+        // end_time is 1_000
+        let mut raffle = snipeable_raffle(60, 120);
+        assert!(apply_anti_snipe(&mut raffle, 939).is_none());
+        assert_eq!(raffle.end_time, 1_000);
+
+        let event = apply_anti_snipe(&mut raffle, 940).unwrap();
+        assert_eq!(event.old_end_time, 1_000);
+        assert_eq!(event.new_end_time, 1_060);
+        assert!(event.anti_snipe);
+        assert_eq!(raffle.end_time, 1_060);
+    }
+
+    #[test]
+    fn anti_snipe_never_shortens_or_runs_when_disabled() {
+        let mut short = snipeable_raffle(60, 30);
+        assert!(apply_anti_snipe(&mut short, 960).is_none());
+        assert!(apply_anti_snipe(&mut short, 970).is_none());
+        assert_eq!(short.end_time, 1_000);
+        assert!(apply_anti_snipe(&mut short, 990).is_some());
+        assert_eq!(short.end_time, 1_020);
+
+        let mut disabled = snipeable_raffle(0, 120);
+        assert!(apply_anti_snipe(&mut disabled, 999).is_none());
+        assert_eq!(disabled.end_time, 1_000);
+    }
+
+    #[test]
+    fn end_time_moves_freely_before_sales() {
+        let mut raffle = legacy_raffle().into_raffle(true);
+
+        assert_eq!(
+            raffle.change_end_time(500, 500).err(),
+            Some(ErrorCode::InvalidEndTime.into())
+        );
+        assert_eq!(
+            raffle.change_end_time(1_000, 500).err(),
+            Some(ErrorCode::InvalidEndTime.into())
+        );
+
+        let event = raffle.change_end_time(800, 500).unwrap();
+        assert_eq!(event.old_end_time, 1_000);
+        assert_eq!(event.new_end_time, 800);
+        assert!(!event.anti_snipe);
+        raffle.change_end_time(2_000, 500).unwrap();
+        assert_eq!(raffle.end_time, 2_000);
+        assert_eq!(raffle.extensions_used, 0);
+    }
+
+    #[test]
+    fn end_time_only_extends_within_limit_after_sales() {
+        let mut raffle = snipeable_raffle(0, 0);
+        raffle.total_num_tickets_bought = 1;
+        raffle.extension_policy.max_extensions = 1;
+
+        assert_eq!(
+            raffle.change_end_time(800, 500).err(),
+            Some(ErrorCode::TicketsAlreadySold.into())
+        );
+        raffle.change_end_time(1_500, 500).unwrap();
+        assert_eq!(raffle.extensions_used, 1);
+        assert_eq!(
+            raffle.change_end_time(2_000, 500).err(),
+            Some(ErrorCode::TooManyExtensions.into())
+        );
+        assert_eq!(raffle.end_time, 1_500);
+    }
+
+    #[test]
+    fn legacy_raffle_state_follows_old_flags() {
+        let cases = [
+            (false, false, false, false, true, RaffleState::Draft),
+            (true, false, false, false, true, RaffleState::Open),
+            (true, false, false, false, false, RaffleState::Draft),
+            (true, true, false, false, true, RaffleState::RandomnessRequested),
+            (false, true, true, false, true, RaffleState::Drawn),
+            (false, true, true, true, true, RaffleState::Settled),
+        ];
+
+        for (is_active, randomness_committed, winner_chosen, claimed, collection, state) in cases {
+            let legacy = LegacyRaffle {
+                is_active,
+                randomness_committed,
+                winner_chosen,
+                claimed,
+                ..legacy_raffle()
+            };
+            assert!(legacy.into_raffle(collection).state == state);
+        }
+    }
+
+    #[test]
+    fn legacy_raffle_keeps_old_fields_and_defaults_new_ones() {
+        let legacy = LegacyRaffle {
+            total_num_tickets_bought: 2,
+            ticket_numbers: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+            ..legacy_raffle()
+        };
+        let authority = legacy.authority;
+        let ticket_numbers = legacy.ticket_numbers.clone();
+
+        let raffle = legacy.into_raffle(false);
+
+        assert_eq!(raffle.authority, authority);
+        assert_eq!(raffle.creator, authority);
+        assert_eq!(raffle.raffle_id, 3);
+        assert_eq!(raffle.price, 100);
+        assert_eq!(raffle.ticket_numbers, ticket_numbers);
+        assert!(!raffle.collection_initialized);
+        assert!(raffle.ticket_mode == TicketMode::Nft);
+        assert_eq!(raffle.nft_config.ticket_name(1), "Token Lottery Ticket #1");
+        assert!(raffle.burn_mint.is_none());
+        assert!(raffle.entry_signer.is_none());
+        assert!(raffle.pricing.curve == PriceCurve::Flat);
+        assert_eq!(raffle.referral_bps, 0);
+        assert_eq!(raffle.registration_period, 0);
+        assert!(raffle.operator.is_none());
+        assert!(!raffle.paused);
+        assert!(raffle.randomness_account.is_none());
+    }
+
+    #[test]
+    fn legacy_raffle_space_matches_original_layout() {
+        // Field sizes of the original `Raffle`, in declaration order
+        let original = 32 + 1 + 8 + 32 + 8 + 8 // authority..end_time
+            + (4 + 50) // name
+            + 1 + 1 + 32 + 8 + 8 // winner_chosen..total_num_tickets_bought
+            + (4 + 100 * 32) // ticket_numbers
+            + 8 + (1 + 8) + 8 + 1 + 1; // max_tickets..randomness_committed
+
+        assert_eq!(LegacyRaffle::INIT_SPACE, original);
+    }
 }
@@ -10,15 +10,12 @@ import {
 import { commitRealRandomness } from "../../lib/commitRealRandomness";
 import { TokenRaffle } from "../../../anchor/target/types/token_raffle";
 import idl from "../../../anchor/target/idl/token_raffle.json";
-import { BN } from "@coral-xyz/anchor";
+import { deriveEventAuthorityPda, deriveProtocolConfigPda } from "../../lib/pda";
 
 export async function POST(req: Request) {
   try {
-    const { raffleId, payer } = await req.json();
-
-    // Convert raffleId to BN/Buffer
-    const raffleIdBn = BN.isBN(raffleId) ? raffleId : new BN(String(raffleId));
-    const raffleIdBuf = raffleIdBn.toArrayLike(Buffer, "le", 8);
+    // `authority` is the raffle authority or operator, who signs the commit
+    const { rafflePda: raffleAddress, authority } = await req.json();
 
     const rpcUrl = process.env.RPC_URL || process.env.NEXT_PUBLIC_RPC;
     console.log("RPC URL:", rpcUrl);
@@ -45,19 +42,20 @@ export async function POST(req: Request) {
     const { randomnessPubkey, instructions, rngKp } = await commitRealRandomness(program);
     console.log("✅ Created randomness pubkey:", randomnessPubkey.toBase58());
 
-    // Step 2 — Build raffle commit instruction
-    const rafflePda = PublicKey.findProgramAddressSync(
-      [Buffer.from("raffle"), new PublicKey(payer).toBuffer(), raffleIdBuf],
-      program.programId
-    )[0];
+    // Step 2 — Build raffle commit instruction. It must land in the same
+    // transaction as the Switchboard commit, which seeds from the previous slot
+    const rafflePda = new PublicKey(raffleAddress);
 
     const raffleIx = await program.methods
       .commitRandomness(false)
       .accounts({
+        authority: new PublicKey(authority),
         raffle: rafflePda,
-        payer: new PublicKey(payer),
+        protocolConfig: deriveProtocolConfigPda(program.programId)[0],
         randomnessDataAccount: randomnessPubkey,
         systemProgram: anchor.web3.SystemProgram.programId,
+        eventAuthority: deriveEventAuthorityPda(program.programId)[0],
+        program: program.programId,
       } as any)
       .instruction();
 
@@ -91,7 +89,7 @@ export async function POST(req: Request) {
 
     /** Prepare commit randomness transaction for Phantom to sign **/
     const tx = new Transaction().add(instructions[1], raffleIx);
-    tx.feePayer = new PublicKey(payer);
+    tx.feePayer = new PublicKey(authority);
     tx.recentBlockhash = (await connection.getLatestBlockhash()).blockhash;
     tx.partialSign(rngKp);
 
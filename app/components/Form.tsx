@@ -2,6 +2,7 @@ import { useState } from "react";
 import { Button } from "./Button";
 import { initConfig } from "../lib/initConfig";
 import { initRaffle } from "../lib/initRaffle";
+import { openRaffle } from "../lib/openRaffle";
 import { PublicKey } from "@solana/web3.js";
 import { useWallet } from "@solana/wallet-adapter-react";
 import { useRaffleProgram } from "../useRaffleProgram";
@@ -10,7 +11,6 @@ import {
   deriveMasterEditionPda,
   deriveMetadataPda,
   deriveMintAuthorityPda,
-  deriveRafflePda,
 } from "../lib/pda";
 import { getAssociatedTokenAddress } from "@solana/spl-token";
 import { BN } from "@coral-xyz/anchor";
@@ -32,33 +32,6 @@ export default function Form() {
     console.log("Submit fired!")
     
     try {
-      // Generate unique raffle ID
-      const raffleId = Date.now();
-      const raffleIdBn = new BN(raffleId);
-
-      // Derive PDAs
-      const [rafflePda] = deriveRafflePda(
-        program.programId,
-        publicKey,
-        raffleIdBn
-      );
-      const [mintAuthorityPda] = deriveMintAuthorityPda(
-        program.programId,
-        rafflePda
-      );
-      const [collectionMintPda] = deriveCollectionMintPda(
-        program.programId,
-        rafflePda
-      );
-      const collectionTokenAccount = await getAssociatedTokenAddress(
-        collectionMintPda,
-        mintAuthorityPda,
-        true // mint authority is PDA
-      );
-
-      const [metadata] = deriveMetadataPda(collectionMintPda);
-      const [masterEdition] = deriveMasterEditionPda(collectionMintPda);
-
       // Convert UI inputs → BN / numbers
       const start = new BN(new Date(startTime).getTime() / 1000); // seconds
       const end = new BN(new Date(endTime).getTime() / 1000);
@@ -85,7 +58,43 @@ export default function Form() {
       maxTicketsBN: maxTicketsBN.toString(),
     });
 
+      // Step 1: Init Config (the registry assigns the raffle id)
+      const { rafflePda, raffleId } = await initConfig(
+        program,
+        raffleName,
+        start,
+        end,
+        priceLamports,
+        maxTicketsBN,
+        {
+          namePrefix: "Token Lottery Ticket #",
+          symbol: "TICKET",
+          collectionUri: "Token Lottery",
+          ticketUri: "Token Lottery",
+          prizeUri: "Token Lottery",
+        },
+        false, // winner is the original buyer
+        publicKey
+      );
+      console.log("✅ Config initialized, raffle id:", raffleId.toString());
+
+      // Derive PDAs
+      const [mintAuthorityPda] = deriveMintAuthorityPda(
+        program.programId,
+        rafflePda
+      );
+      const [collectionMintPda] = deriveCollectionMintPda(
+        program.programId,
+        rafflePda
+      );
+      const collectionTokenAccount = await getAssociatedTokenAddress(
+        collectionMintPda,
+        mintAuthorityPda,
+        true // mint authority is PDA
+      );
 
+      const [metadata] = deriveMetadataPda(collectionMintPda);
+      const [masterEdition] = deriveMasterEditionPda(collectionMintPda);
 
       console.log("🔑 Derived PDAs", {
         rafflePda: rafflePda.toBase58(),
@@ -96,19 +105,6 @@ export default function Form() {
         collectionTokenAccount: collectionTokenAccount.toBase58(),
       });
 
-      // Step 1: Init Config
-      await initConfig(
-        program,
-        raffleIdBn,
-        raffleName,
-        start,
-        end,
-        priceLamports,
-        maxTicketsBN,
-        publicKey
-      );
-      console.log("✅ Config initialized");
-
       // Step 2: Init Raffle
       await initRaffle(
         program,
@@ -119,10 +115,15 @@ export default function Form() {
         collectionTokenAccount,
         metadata,
         masterEdition,
-        new PublicKey("SysvarRent111111111111111111111111111111111")
+        new PublicKey("SysvarRent111111111111111111111111111111111"),
+        { sellerFeeBasisPoints: 0, creators: [] }
       );
       console.log("✅ Raffle initialized");
 
+      // Step 3: Open ticket sales
+      await openRaffle(program, publicKey, rafflePda);
+      console.log("✅ Raffle opened");
+
       setRaffleName("");
     setStartTime("");
     setEndTime("");
@@ -5,13 +5,15 @@ import { Dialog } from "@headlessui/react";
 import { Button } from "./Button";
 import { useWallet } from "@solana/wallet-adapter-react";
 import { useRaffleProgram } from "../useRaffleProgram";
-import { PublicKey, SystemProgram } from "@solana/web3.js";
+import { PublicKey } from "@solana/web3.js";
+import { revealWinner } from "../lib/revealWinner";
 
 type Props = {
   rafflePda: string;
   raffleAuthority: string;
   raffleName: string;
   randomness_committed: boolean;
+  randomnessAccount: string | null;
   raffleWinner?: string | null;
 };
 
@@ -20,6 +22,7 @@ export default function RevealWinnerModal({
   raffleAuthority,
   raffleName,
   randomness_committed,
+  randomnessAccount,
   raffleWinner
 }: Props) {
   const { publicKey } = useWallet();
@@ -34,14 +37,12 @@ export default function RevealWinnerModal({
     setLoading(true);
 
     try {
-      await program.methods
-        .revealWinner()
-        .accounts({
-                authority: publicKey,
-                raffle: new PublicKey(rafflePda),
-                systemProgram: SystemProgram.programId,
-        } as any)
-        .rpc();
+      await revealWinner(
+        program,
+        publicKey,
+        new PublicKey(rafflePda),
+        randomnessAccount ? new PublicKey(randomnessAccount) : null
+      );
 
       alert("🎉 Winner revealed successfully!");
       setIsOpen(false);
@@ -1,10 +1,9 @@
 // hooks/useClaimPrize.ts
 import { useWallet } from "@solana/wallet-adapter-react";
 import { Program } from "@coral-xyz/anchor";
-import { BN } from "@coral-xyz/anchor";
 import { PublicKey, SystemProgram, SYSVAR_RENT_PUBKEY } from "@solana/web3.js";
 import { ASSOCIATED_TOKEN_PROGRAM_ID, getAssociatedTokenAddressSync, TOKEN_PROGRAM_ID } from "@solana/spl-token";
-import { deriveRafflePda } from "../lib/pda";
+import { deriveEventAuthorityPda, deriveMintAuthorityPda, derivePrizeMintPda } from "../lib/pda";
 
 export function useClaimPrize(program: Program<any> | null) {
   const { publicKey } = useWallet();
@@ -14,16 +13,11 @@ export function useClaimPrize(program: Program<any> | null) {
     if (!program) return alert("Program not loaded");
 
     try {
-      const raffleIdBn = BN.isBN(raffle.raffleId) ? raffle.raffleId : new BN(raffle.raffleId);
-
-      // ✅ Derive raffle PDA
-      const [rafflePda] = deriveRafflePda(program.programId, publicKey, raffleIdBn);
+      // ✅ Raffle PDA, seeded by its creator rather than the winner
+      const rafflePda: PublicKey = raffle.pda;
 
       // ✅ Prize mint PDA
-      const [prizeMintPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("prize_mint"), rafflePda.toBuffer()],
-        program.programId
-      );
+      const [prizeMintPda] = derivePrizeMintPda(program.programId, rafflePda);
 
       // ✅ Winner ATA
       const winnerAta = getAssociatedTokenAddressSync(prizeMintPda, publicKey);
@@ -47,20 +41,31 @@ export function useClaimPrize(program: Program<any> | null) {
       );
 
       // ✅ Call claimPrize method
+      // NFT tickets won by the original buyer need none of the optional
+      // holder, compressed or fungible accounts
       await program.methods
-        .claimPrize()
+        .claimPrize(null)
         .accountsStrict({
           raffle: rafflePda,
-          prizeMint: prizeMintPda,
           winner: publicKey,
+          mintAuthority: deriveMintAuthorityPda(program.programId, rafflePda)[0],
+          prizeMint: prizeMintPda,
           winnerAta,
           metadata: nftMetadata,
           masterEdition: nftMasterEdition,
-          tokenProgram: TOKEN_PROGRAM_ID,
           tokenMetadataProgram: tokenMetadataProgramId,
+          winningTicketMint: null,
+          winningTicketAccount: null,
+          ticketTokenProgram: null,
+          ticketSnapshot: null,
+          merkleTree: null,
+          compressionProgram: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
           rent: SYSVAR_RENT_PUBKEY,
+          eventAuthority: deriveEventAuthorityPda(program.programId)[0],
+          program: program.programId,
         } as any)
         .rpc();
 
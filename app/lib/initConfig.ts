@@ -1,37 +1,70 @@
 import { PublicKey, SystemProgram } from "@solana/web3.js";
 import BN from "bn.js";
-import { deriveRafflePda } from "./pda";
+import {
+  deriveAuthorityRafflesPda,
+  deriveEventAuthorityPda,
+  deriveProtocolConfigPda,
+  deriveRaffleIndexPda,
+  deriveRafflePda,
+  deriveRaffleRegistryPda,
+} from "./pda";
+
+export type NftConfig = {
+  namePrefix: string;
+  symbol: string;
+  collectionUri: string;
+  ticketUri: string; // `{id}` is replaced with the ticket index
+  prizeUri: string;
+};
 
 export async function initConfig(
   program: any,         // ✅ accept program as an argument
-  raffleId: BN,
   name: string,
   start: BN,
   end: BN,
   price: BN,
   maxTickets: BN,
-  payer: PublicKey
+  nftConfig: NftConfig,
+  winnerByHolder: boolean,
+  authority: PublicKey
 ) {
   if (!program) throw new Error("Program not available");
 
-  const [rafflePda] = deriveRafflePda(program.programId, payer, raffleId);
+  // The registry assigns the next raffle id, which is part of the raffle PDA
+  const [raffleRegistry] = deriveRaffleRegistryPda(program.programId);
+  const registry = await program.account.raffleRegistry.fetch(raffleRegistry);
+  const raffleId: BN = registry.nextRaffleId;
+
+  const [rafflePda] = deriveRafflePda(program.programId, authority, raffleId);
+  const [raffleIndex] = deriveRaffleIndexPda(program.programId, raffleId);
+  const [authorityRaffles] = deriveAuthorityRafflesPda(program.programId, authority);
+  const [protocolConfig] = deriveProtocolConfigPda(program.programId);
+  const [eventAuthority] = deriveEventAuthorityPda(program.programId);
 
   await program.methods
     .initConfig(
-      new BN(raffleId),
       name,
       new BN(start),
       new BN(end),
       new BN(price),
-      new BN(maxTickets)
+      new BN(maxTickets),
+      nftConfig,
+      winnerByHolder
     )
     .accounts({
-      payer,
+      authority,
+      payer: authority,
+      raffleRegistry,
       raffle: rafflePda,
+      raffleIndex,
+      authorityRaffles,
+      protocolConfig,
       systemProgram: SystemProgram.programId,
+      eventAuthority,
+      program: program.programId,
     } as any)
     .rpc();
 
   console.log("✅ initConfig complete:", rafflePda.toBase58());
-  return rafflePda;
+  return { rafflePda, raffleId };
 }
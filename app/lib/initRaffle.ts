@@ -1,27 +1,34 @@
 import { PublicKey, SystemProgram } from "@solana/web3.js";
 import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
 import { ASSOCIATED_TOKEN_PROGRAM_ID } from "@solana/spl-token";
-import { useRaffleProgram } from "../useRaffleProgram";
+import { deriveEventAuthorityPda, TOKEN_METADATA_PROGRAM_ID } from "./pda";
+
+export type RoyaltyConfig = {
+  sellerFeeBasisPoints: number;
+  creators: { address: PublicKey; share: number }[];
+};
 
 export async function initRaffle(
   program: any,         // ✅ accept program as an argument
-  payer: PublicKey,
+  authority: PublicKey,
   rafflePda: PublicKey,
   mintAuthorityPda: PublicKey,
   collectionMintPda: PublicKey,
   collectionTokenAccount: PublicKey,
   metadata: PublicKey,
   masterEdition: PublicKey,
-  rent: PublicKey
+  rent: PublicKey,
+  royalty: RoyaltyConfig
 ) {
   if (!program) throw new Error("Wallet not connected");
 
-  
+  const [eventAuthority] = deriveEventAuthorityPda(program.programId);
 
   await program.methods
-    .initRaffle()
+    .initRaffle(royalty)
     .accounts({
-      payer,
+      authority,
+      payer: authority,
       raffle: rafflePda,
       mintAuthority: mintAuthorityPda,
       collectionMint: collectionMintPda,
@@ -30,8 +37,11 @@ export async function initRaffle(
       masterEdition,
       tokenProgram: TOKEN_PROGRAM_ID,
       associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
       systemProgram: SystemProgram.programId,
       rent,
+      eventAuthority,
+      program: program.programId,
     }as any)
     .rpc();
 
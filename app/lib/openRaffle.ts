@@ -0,0 +1,20 @@
+import { PublicKey } from "@solana/web3.js";
+
+// Raffles are created as drafts; ticket sales start once the authority opens them
+export async function openRaffle(
+  program: any,
+  authority: PublicKey,
+  rafflePda: PublicKey
+) {
+  if (!program) throw new Error("Wallet not connected");
+
+  await program.methods
+    .openRaffle()
+    .accounts({
+      authority,
+      raffle: rafflePda,
+    } as any)
+    .rpc();
+
+  console.log("✅ openRaffle complete");
+}
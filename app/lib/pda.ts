@@ -5,17 +5,70 @@ export const TOKEN_METADATA_PROGRAM_ID = new PublicKey(
   "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s" // Metaplex Token Metadata program
 );
 
-export function deriveRafflePda(programId: PublicKey, payer: PublicKey, raffleId: BN) {
+// raffle ids are assigned by the registry; `creator` is the authority that created the raffle
+export function deriveRafflePda(programId: PublicKey, creator: PublicKey, raffleId: BN) {
   return PublicKey.findProgramAddressSync(
     [
       Buffer.from("raffle"),
-      payer.toBuffer(),
+      creator.toBuffer(),
 raffleId.toArrayLike(Buffer, "le", 8), // 8-byte little endian
     ],
     programId
   );
 }
 
+export function deriveProtocolConfigPda(programId: PublicKey) {
+  return PublicKey.findProgramAddressSync([Buffer.from("protocol_config")], programId);
+}
+
+export function deriveRaffleRegistryPda(programId: PublicKey) {
+  return PublicKey.findProgramAddressSync([Buffer.from("raffle_registry")], programId);
+}
+
+export function deriveRaffleIndexPda(programId: PublicKey, raffleId: BN) {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("raffle_index"), raffleId.toArrayLike(Buffer, "le", 8)],
+    programId
+  );
+}
+
+export function deriveAuthorityRafflesPda(programId: PublicKey, authority: PublicKey) {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("authority_raffles"), authority.toBuffer()],
+    programId
+  );
+}
+
+// signer for emit_cpi! events
+export function deriveEventAuthorityPda(programId: PublicKey) {
+  return PublicKey.findProgramAddressSync([Buffer.from("__event_authority")], programId);
+}
+
+export function deriveTicketMintPda(programId: PublicKey, rafflePda: PublicKey, ticketIndex: BN) {
+  return PublicKey.findProgramAddressSync(
+    [
+      Buffer.from("ticket_mint"),
+      rafflePda.toBuffer(),
+      ticketIndex.toArrayLike(Buffer, "le", 8),
+    ],
+    programId
+  );
+}
+
+export function deriveParticipantPda(programId: PublicKey, rafflePda: PublicKey, buyer: PublicKey) {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("participant"), rafflePda.toBuffer(), buyer.toBuffer()],
+    programId
+  );
+}
+
+export function derivePrizeMintPda(programId: PublicKey, rafflePda: PublicKey) {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("prize_mint"), rafflePda.toBuffer()],
+    programId
+  );
+}
+
 export function deriveMintAuthorityPda(programId: PublicKey, rafflePda: PublicKey) {
   return PublicKey.findProgramAddressSync(
     [Buffer.from("mint_authority"), rafflePda.toBuffer()],
@@ -1,18 +1,37 @@
-import { Program } from "@coral-xyz/anchor";
-import { PublicKey } from "@solana/web3.js";
+import { AnchorProvider, Program } from "@coral-xyz/anchor";
+import { PublicKey, SystemProgram, Transaction } from "@solana/web3.js";
+import { ON_DEMAND_DEVNET_PID, Randomness } from "@switchboard-xyz/on-demand";
 import { TokenRaffle } from "../../anchor/target/types/token_raffle" // adjust to your IDL path
+import { deriveEventAuthorityPda, deriveProtocolConfigPda } from "./pda";
 
+// `randomnessAccount` is the Switchboard account bound at commit, or null for mock randomness
 export async function revealWinner(
   program: Program<TokenRaffle>,
   authority: PublicKey,
-  rafflePda: PublicKey
+  rafflePda: PublicKey,
+  randomnessAccount: PublicKey | null
 ) {
-  await program.methods
-    .revealWinner()
-    .accounts({
-      raffle: rafflePda,
-      authority,
-      systemProgram: PublicKey.default,
-    } as any)
-    .rpc();
+  const revealWinnerIx = program.methods.revealWinner().accounts({
+    authority,
+    raffle: rafflePda,
+    protocolConfig: deriveProtocolConfigPda(program.programId)[0],
+    randomnessDataAccount: randomnessAccount,
+    systemProgram: SystemProgram.programId,
+    eventAuthority: deriveEventAuthorityPda(program.programId)[0],
+    program: program.programId,
+  } as any);
+
+  if (!randomnessAccount) {
+    return revealWinnerIx.rpc();
+  }
+
+  // The Switchboard value can only be read in the slot it is revealed, so the
+  // oracle's reveal instruction and reveal_winner share one transaction
+  const provider = program.provider as AnchorProvider;
+  const sbProgram = await Program.at(ON_DEMAND_DEVNET_PID, provider);
+  const randomness = new Randomness(sbProgram, randomnessAccount);
+  const revealIx = await randomness.revealIx();
+
+  const tx = new Transaction().add(revealIx, await revealWinnerIx.instruction());
+  return provider.sendAndConfirm(tx);
 }
@@ -13,7 +13,18 @@ import {
   getAssociatedTokenAddressSync,
   TOKEN_PROGRAM_ID,
 } from "@solana/spl-token";
-import { deriveCollectionMintPda, deriveRafflePda } from "./lib/pda";
+import {
+  deriveCollectionMintPda,
+  deriveEventAuthorityPda,
+  deriveMasterEditionPda,
+  deriveMetadataPda,
+  deriveMintAuthorityPda,
+  deriveParticipantPda,
+  derivePrizeMintPda,
+  deriveProtocolConfigPda,
+  deriveTicketMintPda,
+  TOKEN_METADATA_PROGRAM_ID,
+} from "./lib/pda";
 import Form from "./components/Form";
 import RevealWinnerModal from "./components/RevealWinnerModal";
 import Navbar from "./components/Navbar";
@@ -63,10 +74,14 @@ const hasFetchedRaffles = useRef(false);
             raf.account.totalNumTicketsBought.toNumber(),
           endTime: raf.account.endTime.toNumber(), // store raw end_time
           pda: raf.publicKey,
-          randomness_committed: raf.account.randomnessCommitted,
+          // state is an enum object such as `{ open: {} }`
+          randomness_committed: ["randomnessRequested", "drawn", "settled"].includes(
+            Object.keys(raf.account.state)[0]
+          ),
+          randomnessAccount: raf.account.randomnessAccount,
           authority: raf.account.authority.toBase58(),
           raffleWinner: raf.account.winner,
-          isClaimed: raf.account.claimed,
+          isClaimed: "settled" in raf.account.state,
         }));
         setRaffles(parsed);
         hasFetchedRaffles.current = true; // mark as fetched
@@ -94,30 +109,20 @@ const hasFetchedRaffles = useRef(false);
   
  
 
-  const handleBuyTicket = async (raffleId: number | string | BN) => {
+  const handleBuyTicket = async (rafflePda: PublicKey) => {
     if (!publicKey || !connected) {
       alert("Please connect your wallet first!");
       return;
     }
 
     try {
-      const raffleIdBn = BN.isBN(raffleId) ? raffleId : new BN(raffleId);
-
-      const [rafflePda] = deriveRafflePda(
-        program.programId,
-        publicKey,
-        raffleIdBn
-      );
       const raffle = await program.account.raffle.fetch(rafflePda);
       const ticketIndex = new BN(raffle.totalNumTicketsBought.toString());
 
-      const [ticketMintPda] = PublicKey.findProgramAddressSync(
-        [
-          Buffer.from("ticket_mint"),
-          rafflePda.toBuffer(),
-          ticketIndex.toArrayLike(Buffer, "le", 8),
-        ],
-        program.programId
+      const [ticketMintPda] = deriveTicketMintPda(
+        program.programId,
+        rafflePda,
+        ticketIndex
       );
 
       const [collectionMintPda] = deriveCollectionMintPda(
@@ -129,18 +134,49 @@ const hasFetchedRaffles = useRef(false);
         publicKey
       );
 
+      // No allowlist proof or signed entry; the optional badge, gate, burn
+      // payment and referral accounts are only needed when the raffle enables them
       await program.methods
-        .buyTickets()
+        .buyTickets(null, null)
         .accounts({
+          payer: publicKey,
           raffle: rafflePda,
-          buyer: publicKey,
+          protocolConfig: deriveProtocolConfigPda(program.programId)[0],
+          mintAuthority: deriveMintAuthorityPda(program.programId, rafflePda)[0],
           ticketMint: ticketMintPda,
           userTokenAccount,
           mint: collectionMintPda,
+          ticketMetadata: deriveMetadataPda(ticketMintPda)[0],
+          ticketMasterEdition: deriveMasterEditionPda(ticketMintPda)[0],
+          collectionMetadata: deriveMetadataPda(collectionMintPda)[0],
+          collectionMasterEdition: deriveMasterEditionPda(collectionMintPda)[0],
+          participant: deriveParticipantPda(program.programId, rafflePda, publicKey)[0],
+          badge: {
+            badgeMint: null,
+            badgeTokenAccount: null,
+            badgeTokenProgram: null,
+            associatedTokenProgram: null,
+          },
+          gates: {
+            gateNftTokenAccount: null,
+            gateNftMetadata: null,
+            gateTokenAccount: null,
+          },
+          payment: {
+            burnMint: null,
+            burnTokenAccount: null,
+            burnTokenProgram: null,
+          },
+          entry: { instructionsSysvar: null },
+          entryRecord: null,
+          referral: { referral: null, referralVault: null },
           tokenProgram: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
-          system: SystemProgram.programId,
+          tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
           rent: SYSVAR_RENT_PUBKEY,
+          eventAuthority: deriveEventAuthorityPda(program.programId)[0],
+          program: program.programId,
         } as any)
         .rpc();
 
@@ -156,20 +192,10 @@ const handleClaimPrize = async (raffle: any) => {
   if (!publicKey) return alert("Please connect your wallet first.");
 
   try {
-    const raffleIdBn = BN.isBN(raffle.id) ? raffle.id : new BN(raffle.id);
-
-    // ✅ Derive Raffle PDA
-    const [rafflePda] = deriveRafflePda(
-      program.programId,
-      publicKey,
-      raffleIdBn
-    );
+    const rafflePda: PublicKey = raffle.pda;
 
     // ✅ Derive Prize Mint PDA
-    const [prizeMintPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from("prize_mint"), rafflePda.toBuffer()],
-      program.programId
-    );
+    const [prizeMintPda] = derivePrizeMintPda(program.programId, rafflePda);
 
     // ✅ Winner’s ATA for prize mint
     const winnerAta = getAssociatedTokenAddressSync(
@@ -201,21 +227,31 @@ const handleClaimPrize = async (raffle: any) => {
       tokenMetadataProgramId
     );
 
-    // ✅ Send transaction to claim prize
+    // ✅ Send transaction to claim prize (NFT tickets won by the original buyer
+    // need none of the optional holder, compressed or fungible accounts)
     await program.methods
-      .claimPrize()
+      .claimPrize(null)
       .accounts({
         raffle: rafflePda,
-        prizeMint: prizeMintPda,
         winner: publicKey,
+        mintAuthority: deriveMintAuthorityPda(program.programId, rafflePda)[0],
+        prizeMint: prizeMintPda,
         winnerAta,
         metadata: nftMetadata,
         masterEdition: nftMasterEdition,
-        tokenProgram: TOKEN_PROGRAM_ID,
         tokenMetadataProgram: tokenMetadataProgramId,
+        winningTicketMint: null,
+        winningTicketAccount: null,
+        ticketTokenProgram: null,
+        ticketSnapshot: null,
+        merkleTree: null,
+        compressionProgram: null,
+        tokenProgram: TOKEN_PROGRAM_ID,
         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
         rent: SYSVAR_RENT_PUBKEY,
+        eventAuthority: deriveEventAuthorityPda(program.programId)[0],
+        program: program.programId,
       } as any)
       .rpc();
 
@@ -321,6 +357,7 @@ const handleClaimPrize = async (raffle: any) => {
 
 async function commitRandomness(
   raffleId: number | string | BN,
+  rafflePda: PublicKey,
   publicKey: PublicKey,
   program: Program<TokenRaffle>,
   useMock: boolean
@@ -331,27 +368,20 @@ const provider = program.provider as anchor.AnchorProvider;
 if (!provider) throw new Error("Provider not found");
 
 
-    const raffleIdBn = BN.isBN(raffleId) ? raffleId : new BN(raffleId);
     if (!publicKey) throw new Error("Wallet not connected");
 
-
-
-
-    const [rafflePda] = deriveRafflePda(
-      program.programId,
-      publicKey,
-      raffleIdBn
-    );
-
     // 🔹 If mock randomness, short-circuit
     if (useMock) {
       const tx = await program.methods
         .commitRandomness(true)
         .accounts({
+          authority: publicKey,
           raffle: rafflePda,
-          payer: publicKey,
-          randomnessDataAccount: Keypair.generate().publicKey, // dummy
+          protocolConfig: deriveProtocolConfigPda(program.programId)[0],
+          randomnessDataAccount: null,
           systemProgram: SystemProgram.programId,
+          eventAuthority: deriveEventAuthorityPda(program.programId)[0],
+          program: program.programId,
         } as any)
         .rpc();
       console.log("✅ Mock randomness committed:", tx);
@@ -362,8 +392,8 @@ if (!provider) throw new Error("Provider not found");
     method: "POST",
     headers: { "Content-Type": "application/json" },
     body: JSON.stringify({
-      raffleId: String(raffleId),
-      payer: publicKey.toBase58(),
+      rafflePda: rafflePda.toBase58(),
+      authority: publicKey.toBase58(),
     }),
   });
 
@@ -456,7 +486,7 @@ if (!provider) throw new Error("Provider not found");
                 <div className="mt-6 flex flex-col space-y-3 w-full">
                   <Button
                     className="w-full bg-purple-600 text-white hover:bg-purple-700"
-                    onClick={() => handleBuyTicket(raffle.id)}
+                    onClick={() => handleBuyTicket(raffle.pda)}
                   >
                     Buy Ticket
                   </Button>
@@ -469,7 +499,7 @@ if (!provider) throw new Error("Provider not found");
       alert("Please connect your wallet first!");
       return;
     }
-    commitRandomness(raffle.id, publicKey, program,  true); // true = mock
+    commitRandomness(raffle.id, raffle.pda, publicKey, program,  true); // true = mock
   }}
     disabled={loadingRaffleId.raffleId === String(raffle.id) && loadingRaffleId.type === "mock" || raffle.randomness_committed}
 
@@ -485,7 +515,7 @@ if (!provider) throw new Error("Provider not found");
       alert("Please connect your wallet first!");
       return;
     }
-    commitRandomness(raffle.id, publicKey, program,  false); // false = switchboard
+    commitRandomness(raffle.id, raffle.pda, publicKey, program,  false); // false = switchboard
   }}
       disabled={loadingRaffleId.raffleId === String(raffle.id) && loadingRaffleId.type === "real" || raffle.randomness_committed}
 
@@ -500,6 +530,7 @@ if (!provider) throw new Error("Provider not found");
                         raffleAuthority={raffle.authority}
                         raffleName={raffle.name || `Raffle #${raffle.id}`}
                         randomness_committed={raffle.randomness_committed}
+                        randomnessAccount={raffle.randomnessAccount ? raffle.randomnessAccount.toBase58() : null}
                         raffleWinner={raffle.winner ? raffle.winner.toBase58()  ?? raffle.winner : null} // pass actual winner if available
                       />
                     </>